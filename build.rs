@@ -1,9 +1,12 @@
+extern crate alloc;
+
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::{env, fs, iter};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
 use embuild::build::{CInclArgs, LinkArgs};
 use embuild::cargo::workspace_dir;
@@ -11,12 +14,61 @@ use embuild::{cmd, git, git::sdk};
 use pkg_config::Library;
 use tempfile::NamedTempFile;
 
+use config::AppConfig;
+use fingerprint::Fingerprint;
+use idl::{ClusterSide, Idl};
+
+#[allow(dead_code)]
+#[path = "src/idl.rs"]
+mod idl;
+
+#[path = "build/codegen.rs"]
+mod codegen;
+
+#[path = "build/config.rs"]
+mod config;
+
+#[path = "build/features.rs"]
+mod features;
+
+#[path = "build/fingerprint.rs"]
+mod fingerprint;
+
+#[path = "build/model.rs"]
+mod model;
+
 #[cfg(not(any(target_os = "linux", target_os = "espidf")))]
 compile_error!("Currently, `chip-sys` only builds for Linux and ESP-IDF");
 
 const CHIP_PATH: &str = "CHIP_PATH";
 const CHIP_REPOSITORY: &str = "CHIP_REPOSITORY";
 const CHIP_VERSION: &str = "CHIP_VERSION";
+const CHIP_PATCHES: &str = "CHIP_PATCHES";
+const CHIP_ZAP_FILE: &str = "CHIP_ZAP_FILE";
+const CHIP_TEMPLATE_ENDPOINTS: &str = "CHIP_TEMPLATE_ENDPOINTS";
+const CHIP_SERVER_CLUSTERS: &str = "CHIP_SERVER_CLUSTERS";
+const CHIP_DYNAMIC_ENDPOINT_COUNT: &str = "CHIP_DYNAMIC_ENDPOINT_COUNT";
+const CHIP_MAX_FABRICS: &str = "CHIP_MAX_FABRICS";
+const CHIP_SECURE_SESSION_POOL_SIZE: &str = "CHIP_SECURE_SESSION_POOL_SIZE";
+const CHIP_SUBSCRIPTIONS_PER_FABRIC: &str = "CHIP_SUBSCRIPTIONS_PER_FABRIC";
+const CHIP_MAX_EXCHANGE_CONTEXTS: &str = "CHIP_MAX_EXCHANGE_CONTEXTS";
+const CHIP_LOGGING: &str = "CHIP_LOGGING";
+const CHIP_KVS_PATH: &str = "CHIP_KVS_PATH";
+const CHIP_APP_CONFIG: &str = "CHIP_APP_CONFIG";
+const CHIP_EXTRA_SOURCES: &str = "CHIP_EXTRA_SOURCES";
+const CHIP_EXTRA_INCLUDE_DIRS: &str = "CHIP_EXTRA_INCLUDE_DIRS";
+const CHIP_SYSTEM_TOOLS: &str = "CHIP_SYSTEM_TOOLS";
+const CHIP_GN: &str = "CHIP_GN";
+const CHIP_NINJA: &str = "CHIP_NINJA";
+const CHIP_PYTHON: &str = "CHIP_PYTHON";
+const CHIP_ZAP: &str = "CHIP_ZAP";
+const CHIP_PREBUILT_DIR: &str = "CHIP_PREBUILT_DIR";
+const CHIP_EXPORT_PREBUILT_DIR: &str = "CHIP_EXPORT_PREBUILT_DIR";
+const CHIP_PREBUILT_RESOLVE_VERSION: &str = "CHIP_PREBUILT_RESOLVE_VERSION";
+const CHIP_BINDINGS_HEADERS: &str = "CHIP_BINDINGS_HEADERS";
+const CHIP_BINDINGS_TYPES: &str = "CHIP_BINDINGS_TYPES";
+const CHIP_BINDINGS_VARS: &str = "CHIP_BINDINGS_VARS";
+const CHIP_BINDINGS_FUNCTIONS: &str = "CHIP_BINDINGS_FUNCTIONS";
 
 const CHIP_DEFAULT_REPOSITORY: &str = "https://github.com/project-chip/connectedhomeip";
 const CHIP_DEFAULT_VERSION: &str = "branch:v1.0-branch";
@@ -24,85 +76,114 @@ const CHIP_MANAGED_REPO_DIR_BASE: &str = "repos";
 
 const WORKSPACE_INSTALL_DIR: &str = ".embuild/chip";
 
-static TYPES: &[&str] = &[
-    "chip::ChipError",
-    "chip::Span",
-    "chip::ByteSpan",
-    "chip::MutableByteSpan",
-    "chip::DeviceLayer::PlatformManager",
-    "chip::DeviceLayer::ConfigurationManager",
-    "chip::DeviceLayer::ConfigurationManagerImpl",
-    "chip::DeviceLayer::CommissionableDataProvider",
-    "chip::RendezvousInformationFlag",
-    "chip::RendezvousInformationFlags",
-    "chip::Server",
-    "chip::ServerInitParams",
-    "chip::CommonCaseDeviceServerInitParams",
-    "chip::EndpointId",
-    "chip::ClusterId",
-    "chip::CommandId",
-    "chip::DataVersion",
-    "chip::app::CommandHandler",
-    "chip::app::ConcreteCommandPath",
-    "chip::app::Clusters::Actions::Commands::InstantAction::DecodableType",
-    "EmberAfStatus",
-    "EmberAfDeviceType",
-    "EmberAfEndpointType",
-    "EmberAfAttributeMetadata",
-    "EmberAfClusterMask",
-    "EmberAfGenericClusterFunction",
-    "EmberAfCluster",
-];
+const PREGENERATED_DIR: &str = "pregenerated";
 
-static VARS: &[&str] = &[
-    "chip::k.*",
-    "CONFIG_.*",
-    "INET_CONFIG_.*",
-    "CHIP_.*",
-    "ZCL_.*",
-    "EmberAfStatus_.*",
-    "ATTRIBUTE_MASK_.*",
-    "CLUSTER_MASK_.*",
-    "FIXED_ENDPOINT_COUNT",
+const BRIDGE_APP_ZAP_FILE: &str = "examples/bridge-app/bridge-common/bridge-app.zap";
+const BRIDGE_APP_ZAP_DIR: &str = "zzz_generated/bridge-app";
+const BRIDGE_APP_TEMPLATE_ENDPOINTS: &[u16] = &[2];
+
+/// The Rust files generated during the build, and the env vars through which the crate includes them
+const GENERATED_FILES: &[(&str, &str)] = &[
+    ("bindings.rs", "GENERATED_BINDINGS_FILE"),
+    ("endpoints.rs", "GENERATED_ENDPOINTS_FILE"),
+    ("clusters.rs", "GENERATED_CLUSTERS_FILE"),
 ];
 
-static FUNCTIONS: &[&str] = &[
-    "glue::Initialize",
-    "glue::CommonCaseDeviceServerInitParams",
-    "chip::Platform::MemoryInit",
-    "chip::Server::GetInstance",
-    "chip::DeviceLayer::PlatformMgr",
-    "chip::DeviceLayer::ConfigurationMgr",
-    "chip::DeviceLayer::ConfigurationManagerImpl",
-    "chip::Credentials::SetDeviceAttestationCredentialsProvider",
-    "chip::Credentials::Examples::GetExampleDACProvider",
-    "PrintOnboardingCodes",
-    "emberAfEndpointFromIndex",
-    "emberAfFixedEndpointCount",
-    "emberAfEndpointEnableDisable",
-    "emberAfSetDeviceTypeList",
-    "emberAfSetDynamicEndpoint",
-    "emberAfClearDynamicEndpoint",
-    "MatterReportingAttributeChangeCallback",
+/// The server clusters the SDK's `Server`, `lib/glue.cpp` and the bridged dynamic endpoints rely on,
+/// which are never trimmed from the data model
+const REQUIRED_SERVER_CLUSTERS: &[u32] = &[
+    0x001d, // Descriptor
+    0x001f, // AccessControl
+    0x0025, // Actions
+    0x0028, // Basic
+    0x0030, // GeneralCommissioning
+    0x0031, // NetworkCommissioning
+    0x0039, // BridgedDeviceBasic
+    0x003c, // AdministratorCommissioning
+    0x003e, // OperationalCredentials
+    0x003f, // GroupKeyManagement
 ];
 
+// Everything the `chip` module uses is behind the plain C API of `lib/include/glue.h`,
+// plus the build configuration macros of the generated `glue_config.h`
+static TYPES: &[&str] = &["glue_.*"];
+
+static VARS: &[&str] = &["GLUE_.*"];
+
+static FUNCTIONS: &[&str] = &["glue_.*"];
+
 fn main() -> Result<()> {
     build()
 }
 
 fn build() -> Result<()> {
-    let sdk = get_chip()?;
-
     let out_dir = PathBuf::from(std::env::var("OUT_DIR")?).canonicalize()?;
     let chip_out_dir = out_dir.join("chip");
 
-    let sdk_repo = build_chip(&sdk, &chip_out_dir)?;
+    let app_config = get_app_config()?;
+
+    println!("cargo:rerun-if-env-changed=DOCS_RS");
+
+    if cfg!(feature = "check-only") || env::var("DOCS_RS").is_ok() {
+        return use_pregenerated();
+    }
+
+    check_sanitizers()?;
+    check_lto()?;
+
+    println!("cargo:rerun-if-env-changed={CHIP_PREBUILT_DIR}");
+
+    let (includes, libp, metadata) = if let Ok(prebuilt_dir) = env::var(CHIP_PREBUILT_DIR) {
+        let prebuilt_dir = workspace_dir().unwrap().join(prebuilt_dir).canonicalize()?;
+
+        use_prebuilt(&prebuilt_dir, &app_config)?
+    } else {
+        let sdk = get_chip()?;
+
+        let patches = get_patches()?;
+
+        let sdk_repo = get_chip_repo(&sdk, &patches)?;
+        let model = get_data_model(&sdk_repo, &chip_out_dir)?;
+
+        build_chip(&sdk_repo, &model, &app_config, &chip_out_dir)?;
+
+        let includes = get_chip_includes(&sdk_repo, &model, &chip_out_dir)?;
+        let libp = get_chip_lib_paths(&chip_out_dir)?;
+
+        gen_bindings(&includes, &out_dir)?;
+
+        let idl = get_idl(&model)?;
+        gen_endpoints(&model, &idl, &out_dir)?;
+        gen_clusters(&model, &idl, &out_dir)?;
 
-    let includes = get_chip_includes(&sdk_repo, &chip_out_dir)?;
-    let libs = get_chip_libs(&sdk_repo, &chip_out_dir)?;
-    let libp = get_chip_lib_paths(&sdk_repo, &chip_out_dir)?;
+        let fingerprint = get_fingerprint(Some(&sdk_repo), &app_config)?;
+        fs::write(chip_out_dir.join("fingerprint"), fingerprint.to_string())?;
 
-    gen_bindings(&includes, &out_dir)?;
+        let metadata = BuildMetadata {
+            sdk_root: Some(sdk_repo.worktree().canonicalize()?),
+            gn_out_dir: Some(chip_out_dir.clone()),
+            gn_args: format_gn_args(
+                get_gn_args()?
+                    .into_iter()
+                    .chain(get_data_model_gn_args(&model))
+                    .chain(get_extra_sources_gn_args()?),
+            ),
+            app_config_include_dir: chip_out_dir.join("app_config"),
+            dynamic_endpoint_count: app_config.dynamic_endpoint_count,
+        };
+
+        println!("cargo:rerun-if-env-changed={CHIP_EXPORT_PREBUILT_DIR}");
+
+        if let Ok(export_dir) = env::var(CHIP_EXPORT_PREBUILT_DIR) {
+            let export_dir = workspace_dir().unwrap().join(export_dir);
+
+            export_prebuilt(&export_dir, &includes, &chip_out_dir, &out_dir, &metadata)?;
+        }
+
+        (includes, libp, metadata)
+    };
+
+    let libs = get_chip_libs()?;
 
     let incl_args = CInclArgs {
         args: includes
@@ -124,32 +205,189 @@ fn build() -> Result<()> {
     lib_args.propagate();
     lib_args.output();
 
+    metadata.output();
+
     Ok(())
 }
 
-fn build_chip(sdk: &sdk::SdkOrigin, chip_out_dir: &Path) -> Result<git::Repository> {
+/// What crates compiling their own C++ code against the same SDK need to know
+/// about the build, published to them as `DEP_CHIP_*` variables
+struct BuildMetadata {
+    /// Not available for prebuilt builds, unless `CHIP_PATH` is set
+    sdk_root: Option<PathBuf>,
+    /// Not available for prebuilt builds, as only their outputs are exported
+    gn_out_dir: Option<PathBuf>,
+    gn_args: String,
+    app_config_include_dir: PathBuf,
+    dynamic_endpoint_count: u32,
+}
+
+impl BuildMetadata {
+    fn output(&self) {
+        if let Some(sdk_root) = &self.sdk_root {
+            println!("cargo:sdk_root={}", sdk_root.display());
+        }
+
+        if let Some(gn_out_dir) = &self.gn_out_dir {
+            println!("cargo:gn_out_dir={}", gn_out_dir.display());
+        }
+
+        println!("cargo:gn_args={}", self.gn_args);
+        println!(
+            "cargo:app_config_include_dir={}",
+            self.app_config_include_dir.display()
+        );
+        println!(
+            "cargo:dynamic_endpoint_count={}",
+            self.dynamic_endpoint_count
+        );
+    }
+}
+
+fn get_chip_repo(sdk: &sdk::SdkOrigin, patches: &[PathBuf]) -> Result<git::Repository> {
     let sdk_repo = match sdk {
         sdk::SdkOrigin::Managed(remote) => {
             let sdks_root = workspace_dir().unwrap().join(WORKSPACE_INSTALL_DIR);
             fs::create_dir_all(&sdks_root)?;
 
-            remote.open_or_clone(
+            let sdk_repo = remote.open_or_clone(
                 &sdks_root,
                 Default::default(),
                 CHIP_DEFAULT_REPOSITORY,
                 CHIP_MANAGED_REPO_DIR_BASE,
-            )?
+            )?;
+
+            apply_patches(&sdk_repo, patches)?;
+
+            sdk_repo
+        }
+        sdk::SdkOrigin::Custom(repo) => {
+            if !patches.is_empty() {
+                println!(
+                    "cargo:warning=`{CHIP_PATCHES}` is ignored, as `{CHIP_PATH}` is set and the SDK checkout is not managed by `chip-sys`"
+                );
+            }
+
+            repo.clone()
         }
-        sdk::SdkOrigin::Custom(repo) => repo.clone(),
     };
 
+    Ok(sdk_repo)
+}
+
+/// The patch files to apply to the managed SDK checkout, in order
+fn get_patches() -> Result<Vec<PathBuf>> {
+    get_list(CHIP_PATCHES)
+        .into_iter()
+        .map(|patch| {
+            let patch = workspace_dir().unwrap().join(patch).canonicalize()?;
+
+            println!("cargo:rerun-if-changed={}", patch.display());
+
+            Ok(patch)
+        })
+        .collect()
+}
+
+fn get_patches_id(patches: &[PathBuf]) -> Result<Option<String>> {
+    if patches.is_empty() {
+        return Ok(None);
+    }
+
+    let mut content = Vec::new();
+
+    for patch in patches {
+        content.extend(fs::read(patch)?);
+    }
+
+    Ok(Some(format!("{:016x}", fnv1a(&content))))
+}
+
+/// Apply the patches to the managed SDK checkout, unless they are already applied.
+///
+/// The applied series (the checked out commit and the hash of each patch, in order) is
+/// recorded in a stamp in the `.git` dir of the checkout; when it differs from the
+/// requested one, the worktree is reset and the whole series is applied again, as
+/// patches can build on each other
+fn apply_patches(sdk: &git::Repository, patches: &[PathBuf]) -> Result<()> {
+    let worktree = sdk.worktree();
+
+    // Relative to the worktree, unless the `.git` dir is elsewhere
+    let stamp = worktree.join(
+        cmd!(
+            "git",
+            "-C",
+            worktree,
+            "rev-parse",
+            "--git-path",
+            "chip-sys-patches"
+        )
+        .stdout()?
+        .trim(),
+    );
+
+    let mut series = vec![cmd!("git", "-C", worktree, "rev-parse", "HEAD")
+        .stdout()?
+        .trim()
+        .to_owned()];
+
+    for patch in patches {
+        series.push(format!("{:016x}", fnv1a(&fs::read(patch)?)));
+    }
+
+    let series = series.join("\n");
+
+    let applied = fs::read_to_string(&stamp).ok();
+
+    match applied {
+        None if patches.is_empty() => return Ok(()),
+        Some(applied) if applied == series => return Ok(()),
+        _ => (),
+    }
+
+    // Also removes the files added by a previously applied series, but keeps the ignored
+    // ones (like the bootstrapped Pigweed environment)
+    cmd!("git", "-C", worktree, "reset", "--hard").run()?;
+    cmd!("git", "-C", worktree, "clean", "-fd").run()?;
+
+    if stamp.exists() {
+        fs::remove_file(&stamp)?;
+    }
+
+    if patches.is_empty() {
+        return Ok(());
+    }
+
+    for patch in patches {
+        cmd!("git", "-C", worktree, "apply", patch)
+            .run()
+            .map_err(|err| {
+                anyhow!(
+                    "Cannot apply patch {} to the SDK in {}: {err}",
+                    patch.display(),
+                    worktree.display()
+                )
+            })?;
+    }
+
+    fs::write(&stamp, series)?;
+
+    Ok(())
+}
+
+fn build_chip(
+    sdk_repo: &git::Repository,
+    model: &DataModel,
+    app_config: &AppConfig,
+    chip_out_dir: &Path,
+) -> Result<()> {
     let sdk = sdk_repo.worktree().canonicalize()?;
 
     fs::create_dir_all(chip_out_dir)?;
 
     let proj_config_include_dir = chip_out_dir.join("app_config");
 
-    create_app_config(&proj_config_include_dir)?;
+    create_app_config(&proj_config_include_dir, app_config)?;
 
     let lib = PathBuf::from("lib").canonicalize()?;
 
@@ -157,9 +395,142 @@ fn build_chip(sdk: &sdk::SdkOrigin, chip_out_dir: &Path) -> Result<git::Reposito
     let libd = lib.display();
     let chip_out_dird = chip_out_dir.display();
     let proj_config_include_dird = proj_config_include_dir.display();
+    let zap_filed = model.zap_file.display();
+    let zap_generated_dird = model.zap_generated_dir().display().to_string();
+
+    let tools = get_tools(model)?;
+
+    let gnd = tools.gn.display();
+    let ninjad = tools.ninja.display();
+    let pythond = tools.python.display();
+
+    let (bootstrap, gn_flags) = if !tools.system {
+        // Bootstrapping the Pigweed environment is what provides the tools otherwise
+        (format!(". {sdkd}/scripts/activate.sh; "), String::new())
+    } else {
+        // `generate.py` looks for `zap-cli` in `ZAP_INSTALL_PATH`
+        let zap = tools
+            .zap
+            .as_ref()
+            .map(|zap| {
+                format!(
+                    "export ZAP_INSTALL_PATH={}; ",
+                    zap.parent().unwrap().display()
+                )
+            })
+            .unwrap_or_default();
+
+        (zap, format!(" --script-executable={pythond}"))
+    };
+
+    let zap_codegen = if model.codegen {
+        format!(
+            "{pythond} {sdkd}/scripts/tools/zap/generate.py {zap_filed} -o {zap_generated_dird}; "
+        )
+    } else {
+        String::new()
+    };
+
+    let gn_args = format_gn_args(
+        get_gn_args()?
+            .into_iter()
+            .chain(get_data_model_gn_args(model))
+            .chain(get_extra_sources_gn_args()?),
+    );
 
     let mut script = NamedTempFile::new()?;
 
+    write!(
+        &mut script,
+        "set -e; \
+         export CHIP_PATH={sdkd}; \
+         export PROJ_CONFIG_INCLUDE_PATH={proj_config_include_dird}; \
+         {bootstrap}\
+         {zap_codegen}\
+         cd {libd}; \
+         {gnd} gen {chip_out_dird}{gn_flags} '--args={gn_args}'; \
+         {ninjad} -C {chip_out_dird}; \
+         cd ..",
+    )?;
+    script.flush()?;
+
+    cmd!("bash", script.path()).run()?;
+
+    Ok(())
+}
+
+/// The tools the CHIPALL build runs
+struct Tools {
+    /// Whether the tools are the ones installed on the build machine, rather than the ones
+    /// of the Pigweed environment bootstrapped by `scripts/activate.sh`
+    system: bool,
+    gn: PathBuf,
+    ninja: PathBuf,
+    python: PathBuf,
+    /// The ZAP tool (`zap-cli`), if the data model needs code generation
+    zap: Option<PathBuf>,
+}
+
+/// With `CHIP_SYSTEM_TOOLS` set, find the tools in `CHIP_GN`, `CHIP_NINJA`, `CHIP_PYTHON`
+/// and `CHIP_ZAP`, or else in `PATH`
+fn get_tools(model: &DataModel) -> Result<Tools> {
+    fn find_tool(var: &str, names: &[&str]) -> Result<PathBuf> {
+        println!("cargo:rerun-if-env-changed={var}");
+
+        if let Ok(tool) = env::var(var) {
+            let tool = workspace_dir()
+                .ok_or_else(|| anyhow!("`{var}`: cannot find the workspace root"))?
+                .join(tool);
+
+            if !tool.is_file() {
+                anyhow::bail!("`{var}`: `{}` does not exist", tool.display());
+            }
+
+            return Ok(tool);
+        }
+
+        env::var_os("PATH")
+            .iter()
+            .flat_map(env::split_paths)
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|tool| tool.is_file())
+            .ok_or_else(|| {
+                anyhow!(
+                    "`{}` not found in `PATH`: install it, or set `{var}` to its location",
+                    names[0]
+                )
+            })
+    }
+
+    if !get_bool(CHIP_SYSTEM_TOOLS)? {
+        return Ok(Tools {
+            system: false,
+            gn: PathBuf::from("gn"),
+            ninja: PathBuf::from("ninja"),
+            python: PathBuf::from("python3"),
+            zap: None,
+        });
+    }
+
+    Ok(Tools {
+        system: true,
+        gn: find_tool(CHIP_GN, &["gn"])?,
+        ninja: find_tool(CHIP_NINJA, &["ninja"])?,
+        python: find_tool(CHIP_PYTHON, &["python3", "python"])?,
+        zap: if model.codegen {
+            Some(find_tool(CHIP_ZAP, &["zap-cli"])?)
+        } else {
+            None
+        },
+    })
+}
+
+/// The GN args of the CHIPALL build, except for the ones pointing at locations
+/// on the build machine
+///
+/// A map, as several features might need the same arg (e.g. `is_clang`), which GN
+/// does not allow to be set more than once.
+fn get_gn_args() -> Result<BTreeMap<&'static str, String>> {
     let arg_debug = env::var("PROFILE")?.eq_ignore_ascii_case("debug");
 
     #[cfg(target_os = "linux")]
@@ -192,61 +563,687 @@ fn build_chip(sdk: &sdk::SdkOrigin, chip_out_dir: &Path) -> Result<git::Reposito
     #[cfg(not(feature = "tcp"))]
     let arg_tcp = false;
 
-    write!(
-        &mut script,
-        "set -e; \
-         export CHIP_PATH={sdkd}; \
-         export PROJ_CONFIG_INCLUDE_PATH={proj_config_include_dird}; \
-         . {sdkd}/scripts/activate.sh; \
-         cd {libd}; \
-         gn gen \
-            {chip_out_dird} \
-            '--args= \
-                is_debug={arg_debug} \
-                standalone={arg_standalone}
-                chip_config_network_layer_ble={arg_ble} \
-                chip_enable_wifi={arg_wifi} \
-                chip_enable_openthread={arg_thread} \
-                chip_inet_config_enable_ipv4={arg_ipv4} \
-                chip_inet_config_enable_tcp_endpoint={arg_tcp} \
-            '; \
-         ninja -C {chip_out_dird}; \
-         cd ..",
+    let mut args = BTreeMap::from([
+        ("is_debug", arg_debug.to_string()),
+        ("standalone", arg_standalone.to_string()),
+        ("chip_config_network_layer_ble", arg_ble.to_string()),
+        ("chip_enable_wifi", arg_wifi.to_string()),
+        ("chip_enable_openthread", arg_thread.to_string()),
+        ("chip_inet_config_enable_ipv4", arg_ipv4.to_string()),
+        ("chip_inet_config_enable_tcp_endpoint", arg_tcp.to_string()),
+    ]);
+
+    let sanitizers = [
+        (cfg!(feature = "asan"), "is_asan"),
+        (cfg!(feature = "ubsan"), "is_ubsan"),
+        (cfg!(feature = "tsan"), "is_tsan"),
+    ];
+
+    for (_, arg) in sanitizers.iter().filter(|(enabled, _)| *enabled) {
+        args.insert(*arg, "true".to_owned());
+    }
+
+    if sanitizers.iter().any(|(enabled, _)| *enabled) {
+        // The sanitizer runtimes are the LLVM ones rustc links in, so the C++ code
+        // needs to be instrumented by clang as well
+        args.insert("is_clang", "true".to_owned());
+    }
+
+    args.extend(get_logging_gn_args()?);
+
+    if cfg!(feature = "lto") {
+        // Size optimization and symbols follow the Rust profile, as the C++ code
+        // is only optimized when rustc links it with the Rust code
+        let opt_level = env::var("OPT_LEVEL")?;
+        let debug = env::var("DEBUG")?.eq_ignore_ascii_case("true");
+
+        args.extend([
+            ("is_clang", "true".to_owned()),
+            ("default_configs_extra", "[\"//:lto\"]".to_owned()),
+            (
+                "optimize_for_size",
+                (opt_level == "s" || opt_level == "z").to_string(),
+            ),
+            ("symbol_level", if debug { "2" } else { "0" }.to_owned()),
+            ("strip_symbols", (!debug).to_string()),
+        ]);
+    }
+
+    Ok(args)
+}
+
+/// The GN args selecting the log categories compiled into CHIPALL with `CHIP_LOGGING`;
+/// none (i.e. the SDK defaults) if it is not set
+fn get_logging_gn_args() -> Result<Vec<(&'static str, String)>> {
+    const CATEGORIES: &[(&str, &str)] = &[
+        ("error", "chip_error_logging"),
+        ("progress", "chip_progress_logging"),
+        ("detail", "chip_detail_logging"),
+        ("automation", "chip_automation_logging"),
+    ];
+
+    let enabled = get_list(CHIP_LOGGING);
+
+    if env::var(CHIP_LOGGING).is_err() {
+        return Ok(Vec::new());
+    }
+
+    if let Some(category) = enabled
+        .iter()
+        .find(|category| !CATEGORIES.iter().any(|(name, _)| name == category))
+    {
+        anyhow::bail!(
+            "Unknown log category `{category}` in `{CHIP_LOGGING}`, expected `error`, `progress`, `detail` or `automation`"
+        );
+    }
+
+    Ok(CATEGORIES
+        .iter()
+        .map(|(name, arg)| {
+            (
+                *arg,
+                enabled.iter().any(|category| category == name).to_string(),
+            )
+        })
+        .collect())
+}
+
+/// The GN args pointing the CHIPALL build at the data model
+fn get_data_model_gn_args(model: &DataModel) -> Vec<(&'static str, String)> {
+    vec![
+        ("chip_zap_file", format!("\"{}\"", model.zap_file.display())),
+        (
+            "chip_zap_pregenerated_dir",
+            format!("\"{}\"", model.zap_generated_dir().display()),
+        ),
+    ]
+}
+
+/// Project-specific C++ sources and include dirs to compile into CHIPALL
+/// alongside `lib/glue.cpp`
+struct ExtraSources {
+    sources: Vec<PathBuf>,
+    include_dirs: Vec<PathBuf>,
+}
+
+impl ExtraSources {
+    /// A hash of the contents of the sources and the include dirs, as their
+    /// locations differ from one workspace to another
+    fn content_hash(&self) -> Result<u64> {
+        let mut files = self.sources.clone();
+
+        for include_dir in &self.include_dirs {
+            collect_files(include_dir, &mut files)?;
+        }
+
+        let mut data = Vec::new();
+
+        for file in &files {
+            data.extend(fs::read(file)?);
+        }
+
+        Ok(fnv1a(&data))
+    }
+}
+
+fn get_extra_sources() -> Result<ExtraSources> {
+    let resolve = |var| {
+        get_list(var)
+            .into_iter()
+            .map(|path| {
+                let path = workspace_dir().unwrap().join(path);
+
+                println!("cargo:rerun-if-changed={}", path.display());
+
+                path.canonicalize()
+                    .map_err(|err| anyhow!("Cannot find {} from `{var}`: {err}", path.display()))
+            })
+            .collect::<Result<Vec<_>>>()
+    };
+
+    Ok(ExtraSources {
+        sources: resolve(CHIP_EXTRA_SOURCES)?,
+        include_dirs: resolve(CHIP_EXTRA_INCLUDE_DIRS)?,
+    })
+}
+
+fn get_extra_sources_gn_args() -> Result<Vec<(&'static str, String)>> {
+    let extra = get_extra_sources()?;
+
+    let gn_list = |paths: &[PathBuf]| {
+        format!(
+            "[{}]",
+            paths
+                .iter()
+                .map(|path| format!("\"{}\"", path.display()))
+                .collect::<Vec<_>>()
+                .join(",")
+        )
+    };
+
+    Ok(vec![
+        ("chip_extra_sources", gn_list(&extra.sources)),
+        ("chip_extra_include_dirs", gn_list(&extra.include_dirs)),
+    ])
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    entries.sort();
+
+    for entry in entries {
+        if entry.is_dir() {
+            collect_files(&entry, files)?;
+        } else {
+            files.push(entry);
+        }
+    }
+
+    Ok(())
+}
+
+fn format_gn_args(args: impl IntoIterator<Item = (&'static str, String)>) -> String {
+    args.into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Check that the Rust code is instrumented with the same sanitizers as the C++ code,
+/// and link the sanitizer runtimes rustc does not provide
+fn check_sanitizers() -> Result<()> {
+    if cfg!(feature = "asan") && cfg!(feature = "tsan") {
+        anyhow::bail!("Features `asan` and `tsan` cannot be enabled at the same time");
+    }
+
+    let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS")
+        .unwrap_or_default()
+        .replace('\x1f', " ");
+
+    for (enabled, feature, sanitizer) in [
+        (cfg!(feature = "asan"), "asan", "address"),
+        (cfg!(feature = "tsan"), "tsan", "thread"),
+    ] {
+        if enabled && !rustflags.contains(&format!("sanitizer={sanitizer}")) {
+            anyhow::bail!(
+                "Feature `{feature}` requires the Rust code to be instrumented too: \
+                 build with a nightly toolchain, `RUSTFLAGS=\"-Zsanitizer={sanitizer}\"` \
+                 and an explicit `--target`"
+            );
+        }
+    }
+
+    // rustc has no UBSan, hence no UBSan runtime either, unless ASan or TSan
+    // (which include the UBSan handlers) are on
+    if cfg!(feature = "ubsan") && !cfg!(feature = "asan") && !cfg!(feature = "tsan") {
+        let arch = env::var("CARGO_CFG_TARGET_ARCH")?;
+
+        for runtime in ["ubsan_standalone", "ubsan_standalone_cxx"] {
+            let lib = format!("clang_rt.{runtime}-{arch}");
+
+            let path = PathBuf::from(
+                cmd!("clang", format!("--print-file-name=lib{lib}.a"))
+                    .stdout()?
+                    .trim(),
+            );
+
+            if !path.is_absolute() {
+                anyhow::bail!("Cannot find the UBSan runtime `lib{lib}.a` of clang");
+            }
+
+            println!(
+                "cargo:rustc-link-search=native={}",
+                path.parent().unwrap().display()
+            );
+            println!("cargo:rustc-link-lib=static={lib}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that rustc links the LLVM bitcode CHIPALL consists of with the `lto` feature
+fn check_lto() -> Result<()> {
+    if cfg!(feature = "lto") {
+        let rustflags = env::var("CARGO_ENCODED_RUSTFLAGS")
+            .unwrap_or_default()
+            .replace('\x1f', " ");
+
+        if !rustflags.contains("linker-plugin-lto") {
+            anyhow::bail!(
+                "Feature `lto` requires cross-language LTO on the Rust side: build with \
+                 `RUSTFLAGS=\"-Clinker-plugin-lto -Clinker=clang -Clink-arg=-fuse-ld=lld\"`, \
+                 with a clang of the same LLVM version as rustc"
+            );
+        }
+    }
+
+    Ok(())
+}
+
+fn get_fingerprint(sdk: Option<&git::Repository>, app_config: &AppConfig) -> Result<Fingerprint> {
+    let sdk_ref = if env::var(CHIP_PATH).is_ok() {
+        // Checkout location is machine-specific, so only the commit identifies it
+        "custom".to_owned()
+    } else {
+        env::var(CHIP_VERSION).unwrap_or(CHIP_DEFAULT_VERSION.to_owned())
+    };
+
+    let sdk_commit = get_sdk_commit(sdk)?;
+
+    let patches = get_patches_id(&get_patches()?)?.unwrap_or_default();
+
+    let data_model = if let Ok(zap_file) = env::var(CHIP_ZAP_FILE) {
+        let zap_file = workspace_dir().unwrap().join(zap_file);
+
+        format!("{:016x}", fnv1a(&fs::read(zap_file)?))
+    } else {
+        "bridge-app".to_owned()
+    };
+
+    let gn_args = format_gn_args(get_gn_args()?);
+
+    let mut features = env::vars()
+        .filter_map(|(var, _)| var.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .collect::<Vec<_>>();
+    features.sort();
+
+    let bindings = [
+        CHIP_BINDINGS_HEADERS,
+        CHIP_BINDINGS_TYPES,
+        CHIP_BINDINGS_FUNCTIONS,
+        CHIP_BINDINGS_VARS,
+    ]
+    .iter()
+    .map(|var| get_list(var).join(","))
+    .collect::<Vec<_>>()
+    .join(";");
+
+    let mut fingerprint = vec![("sdk-ref", sdk_ref)];
+
+    if let Some(sdk_commit) = sdk_commit {
+        fingerprint.push(("sdk-commit", sdk_commit));
+    }
+
+    fingerprint.extend([
+        ("patches", patches),
+        ("target", env::var("TARGET")?),
+        ("gn-args", gn_args),
+        ("features", features.join(",")),
+        (
+            "app-config",
+            format!("{:016x}", fnv1a(app_config.header().as_bytes())),
+        ),
+        ("data-model", data_model),
+        (
+            "template-endpoints",
+            get_list(CHIP_TEMPLATE_ENDPOINTS).join(","),
+        ),
+        ("bindings", bindings),
+    ]);
+
+    if env::var(CHIP_SERVER_CLUSTERS).is_ok() {
+        fingerprint.push(("server-clusters", get_list(CHIP_SERVER_CLUSTERS).join(",")));
+    }
+
+    let extra = get_extra_sources()?;
+
+    if !extra.sources.is_empty() || !extra.include_dirs.is_empty() {
+        fingerprint.push(("extra-sources", format!("{:016x}", extra.content_hash()?)));
+    }
+
+    Ok(Fingerprint(fingerprint))
+}
+
+/// The commit of the SDK.
+///
+/// Without a checkout (i.e. when checking a prebuilt CHIP against a managed SDK which is not
+/// downloaded), this is the `CHIP_VERSION` ref if it is a commit. A branch or a tag is only
+/// resolved remotely with `CHIP_PREBUILT_RESOLVE_VERSION` set; otherwise, the commit is not known,
+/// and the one recorded in the prebuilt CHIP is trusted to match the `CHIP_VERSION` ref.
+fn get_sdk_commit(sdk: Option<&git::Repository>) -> Result<Option<String>> {
+    if let Some(sdk) = sdk {
+        return Ok(Some(
+            cmd!("git", "-C", sdk.worktree(), "rev-parse", "HEAD")
+                .stdout()?
+                .trim()
+                .to_owned(),
+        ));
+    }
+
+    let repository =
+        env::var(CHIP_REPOSITORY).unwrap_or_else(|_| CHIP_DEFAULT_REPOSITORY.to_owned());
+    let version = env::var(CHIP_VERSION).unwrap_or_else(|_| CHIP_DEFAULT_VERSION.to_owned());
+
+    // Annotated tags are listed both as themselves and as the commit they point to (`^{}`)
+    let refs = match git::Ref::parse(&version) {
+        git::Ref::Commit(commit) => return Ok(Some(commit)),
+        git::Ref::Branch(branch) => vec![format!("refs/heads/{branch}")],
+        git::Ref::Tag(tag) => vec![format!("refs/tags/{tag}^{{}}"), format!("refs/tags/{tag}")],
+    };
+
+    if !get_bool(CHIP_PREBUILT_RESOLVE_VERSION)? {
+        return Ok(None);
+    }
+
+    let remote = cmd!("git", "ls-remote", "--heads", "--tags", &repository)
+        .stdout()
+        .map_err(|err| {
+            anyhow!(
+                "`{CHIP_PREBUILT_RESOLVE_VERSION}`: cannot list the refs of {repository}: {err}"
+            )
+        })?;
+
+    refs.iter()
+        .find_map(|git_ref| {
+            remote.lines().find_map(|line| {
+                line.split_once('\t')
+                    .filter(|(_, name)| name == git_ref)
+                    .map(|(commit, _)| commit.to_owned())
+            })
+        })
+        .map(Some)
+        .ok_or_else(|| {
+            anyhow!(
+                "`{CHIP_PREBUILT_RESOLVE_VERSION}`: `{version}` not found in {repository}; \
+                 set `{CHIP_VERSION}` to `commit:<sha>`, or `{CHIP_PATH}` to the SDK checkout"
+            )
+        })
+}
+
+/// A simple, stable across Rust releases hash for the fingerprint
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Use a CHIPALL build exported with `CHIP_EXPORT_PREBUILT_DIR`, checking that it
+/// matches the current configuration
+fn use_prebuilt(
+    prebuilt_dir: &Path,
+    app_config: &AppConfig,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>, BuildMetadata)> {
+    let fingerprint_file = prebuilt_dir.join("fingerprint");
+
+    println!("cargo:rerun-if-changed={}", fingerprint_file.display());
+
+    let prebuilt = Fingerprint::parse(&fs::read_to_string(&fingerprint_file).map_err(|err| {
+        anyhow!(
+            "Cannot read the fingerprint {} of the prebuilt CHIP: {err}",
+            fingerprint_file.display()
+        )
+    })?);
+
+    // Without an SDK checkout, the SDK commit is only known if `CHIP_VERSION` is one,
+    // or if resolving it remotely is asked for
+    let sdk = env::var(CHIP_PATH)
+        .ok()
+        .map(|sdk| git::Repository::new(PathBuf::from(sdk)));
+
+    let expected = get_fingerprint(sdk.as_ref(), app_config)?;
+
+    let mismatches = expected.mismatches(&prebuilt);
+
+    if !mismatches.is_empty() {
+        anyhow::bail!(
+            "The prebuilt CHIP in {} does not match the current build configuration:\n{}\n\
+             Rebuild it with `{CHIP_EXPORT_PREBUILT_DIR}` or unset `{CHIP_PREBUILT_DIR}`",
+            prebuilt_dir.display(),
+            mismatches.join("\n")
+        );
+    }
+
+    for (file, var) in GENERATED_FILES {
+        let path = prebuilt_dir.join(file);
+
+        println!("cargo:rerun-if-changed={}", path.display());
+        println!("cargo:rustc-env={var}={}", path.display());
+    }
+
+    let mut includes = fs::read_dir(prebuilt_dir.join("include"))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    includes.sort();
+
+    let libp = get_chip_lib_paths(prebuilt_dir)?;
+
+    // The build metadata is republished as exported, since it cannot be derived from
+    // the current configuration (e.g. the GN args of the data model of the exporting build)
+    let metadata_file = prebuilt_dir.join("metadata");
+
+    println!("cargo:rerun-if-changed={}", metadata_file.display());
+
+    let exported = Fingerprint::parse(&fs::read_to_string(&metadata_file).map_err(|err| {
+        anyhow!(
+            "Cannot read the build metadata {} of the prebuilt CHIP: {err}",
+            metadata_file.display()
+        )
+    })?);
+
+    let exported = |key: &str| {
+        exported
+            .iter()
+            .find(|(ekey, _)| ekey == key)
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| {
+                anyhow!(
+                    "The build metadata {} of the prebuilt CHIP has no `{key}`",
+                    metadata_file.display()
+                )
+            })
+    };
+
+    let metadata = BuildMetadata {
+        sdk_root: sdk.map(|sdk| sdk.worktree().canonicalize()).transpose()?,
+        gn_out_dir: None,
+        gn_args: exported("gn-args")?,
+        app_config_include_dir: prebuilt_dir.join(exported("app-config-include-dir")?),
+        dynamic_endpoint_count: app_config.dynamic_endpoint_count,
+    };
+
+    Ok((includes, libp, metadata))
+}
+
+/// Use the checked-in bindings of the default feature set, without building
+/// (or linking against) the SDK; good enough for `cargo doc` and `cargo check`
+fn use_pregenerated() -> Result<()> {
+    let pregenerated_dir = PathBuf::from(PREGENERATED_DIR).canonicalize()?;
+
+    for (file, _) in GENERATED_FILES {
+        let path = pregenerated_dir.join(file);
+
+        if !path.exists() {
+            anyhow::bail!(
+                "The pregenerated {} is missing; generate it by building with the default features and `{CHIP_EXPORT_PREBUILT_DIR}` set, \
+                 and then copy it from the export directory into `{PREGENERATED_DIR}`",
+                path.display()
+            );
+        }
+
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+
+    let manifest = PathBuf::from(env::var("CARGO_MANIFEST_DIR")?).join("Cargo.toml");
+
+    println!("cargo:rerun-if-changed={}", manifest.display());
+
+    // As in the `CARGO_FEATURE_*` variables
+    let default_features = features::default_features(&fs::read_to_string(&manifest)?)
+        .map_err(|err| anyhow!("{}: {err}", manifest.display()))?
+        .into_iter()
+        .map(|feature| feature.replace('-', "_"))
+        .collect::<Vec<_>>();
+
+    let non_default_features = env::vars()
+        .filter_map(|(var, _)| var.strip_prefix("CARGO_FEATURE_").map(str::to_lowercase))
+        .filter(|feature| feature != "check_only" && !default_features.contains(feature))
+        .collect::<Vec<_>>();
+
+    if !non_default_features.is_empty() {
+        println!(
+            "cargo:warning=Using the pregenerated bindings of the default feature set; constants might not reflect features {}",
+            non_default_features.join(", ")
+        );
+    }
+
+    for (file, var) in GENERATED_FILES {
+        println!(
+            "cargo:rustc-env={var}={}",
+            pregenerated_dir.join(file).display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Export the CHIPALL build, its headers and the generated Rust code into `export_dir`,
+/// for use with `CHIP_PREBUILT_DIR`
+fn export_prebuilt(
+    export_dir: &Path,
+    includes: &[PathBuf],
+    chip_out_dir: &Path,
+    out_dir: &Path,
+    metadata: &BuildMetadata,
+) -> Result<()> {
+    fn copy_headers(from: &Path, to: &Path) -> Result<()> {
+        for entry in fs::read_dir(from)? {
+            let path = entry?.path();
+
+            if path.is_dir() {
+                copy_headers(&path, &to.join(path.file_name().unwrap()))?;
+            } else if matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("h" | "hpp" | "ipp" | "inc" | "def")
+            ) {
+                fs::create_dir_all(to)?;
+                fs::copy(&path, to.join(path.file_name().unwrap()))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    if export_dir.exists() {
+        fs::remove_dir_all(export_dir)?;
+    }
+
+    fs::create_dir_all(export_dir)?;
+
+    // Keep the order of the include paths, as headers might shadow each other
+    for (index, include) in includes.iter().enumerate() {
+        if include.is_dir() {
+            copy_headers(
+                include,
+                &export_dir.join("include").join(format!("{index:03}")),
+            )?;
+        }
+    }
+
+    fs::copy(
+        chip_out_dir.join("libCHIPALL.a"),
+        export_dir.join("libCHIPALL.a"),
+    )?;
+    fs::copy(
+        chip_out_dir.join("fingerprint"),
+        export_dir.join("fingerprint"),
     )?;
-    script.flush()?;
 
-    cmd!("bash", script.path()).run()?;
+    let app_config_include_dir = includes
+        .iter()
+        .position(|include| *include == metadata.app_config_include_dir)
+        .ok_or_else(|| anyhow!("The app config dir is not among the include paths"))?;
+
+    fs::write(
+        export_dir.join("metadata"),
+        format!(
+            "gn-args={}\napp-config-include-dir=include/{app_config_include_dir:03}\n",
+            metadata.gn_args
+        ),
+    )?;
 
-    Ok(sdk_repo)
+    for (file, _) in GENERATED_FILES {
+        fs::copy(out_dir.join(file), export_dir.join(file))?;
+    }
+
+    Ok(())
 }
 
 fn gen_bindings(includes: &[impl AsRef<Path>], out_dir: &Path) -> Result<()> {
-    let header = "src/include/bindings.h";
+    let header = "lib/include/glue.h";
+
+    let glue_config_file = gen_glue_config(includes, out_dir)?;
+
+    // Our own headers, plus any headers the dependent crates need bindings for
+    let mut bindings_header = format!(
+        "#include \"{}\"\n#include \"{}\"\n",
+        PathBuf::from(header).canonicalize()?.display(),
+        glue_config_file.display()
+    );
+
+    let extra_headers = get_list(CHIP_BINDINGS_HEADERS);
+
+    for extra_header in &extra_headers {
+        let path = workspace_dir().unwrap().join(extra_header);
+
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+
+            bindings_header.push_str(&format!("#include \"{}\"\n", path.display()));
+        } else {
+            // Not a file in the workspace, so a header from the SDK include paths
+            bindings_header.push_str(&format!("#include <{extra_header}>\n"));
+        }
+    }
+
+    let bindings_header_file = out_dir.join("bindings.h");
+    fs::write(&bindings_header_file, bindings_header)?;
+
+    let types = get_list(CHIP_BINDINGS_TYPES);
+    let functions = get_list(CHIP_BINDINGS_FUNCTIONS);
+    let vars = get_list(CHIP_BINDINGS_VARS);
+
+    // Only the SDK classes the dependent crates ask for need C++; our own headers are plain C
+    let sdk_classes = !extra_headers.is_empty() || !types.is_empty() || !functions.is_empty();
+
+    let lang_args: &[&str] = if sdk_classes {
+        &["-x", "c++", "-std=c++14"]
+    } else {
+        &["-x", "c"]
+    };
 
     let mut bindgen = bindgen::Builder::default()
-        .generate_inline_functions(true)
         .use_core()
+        .header(bindings_header_file.display().to_string())
+        .parse_callbacks(Box::new(GlueCallbacks))
+        .clang_args(lang_args)
+        .generate_inline_functions(sdk_classes)
         .enable_function_attribute_detection()
-        //.parse_callbacks(Box::new(BindgenCallbacks))
-        .header(header)
-        .clang_arg("-x")
-        .clang_arg("c++")
-        .clang_arg("-std=c++14")
-        //.clang_arg("-Wno-unused")
-        .vtable_generation(true)
+        .vtable_generation(sdk_classes)
         .opaque_type("std::.*");
 
-    for typ in TYPES {
+    for typ in TYPES
+        .iter()
+        .copied()
+        .chain(types.iter().map(String::as_str))
+    {
         bindgen = bindgen
             .allowlist_type(typ)
             .allowlist_function(format!("{typ}_.*"));
     }
 
-    for function in FUNCTIONS {
+    for function in FUNCTIONS
+        .iter()
+        .copied()
+        .chain(functions.iter().map(String::as_str))
+    {
         bindgen = bindgen.allowlist_function(function);
     }
 
-    for var in VARS {
+    for var in VARS.iter().copied().chain(vars.iter().map(String::as_str)) {
         bindgen = bindgen.allowlist_var(var);
     }
 
@@ -269,6 +1266,96 @@ fn gen_bindings(includes: &[impl AsRef<Path>], out_dir: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Types the `GLUE_EMBER_ZCL_STATUS_*` macros as `glue_EmberAfStatus`, like the functions
+/// returning them
+#[derive(Debug)]
+struct GlueCallbacks;
+
+impl bindgen::callbacks::ParseCallbacks for GlueCallbacks {
+    fn int_macro(&self, name: &str, _value: i64) -> Option<bindgen::callbacks::IntKind> {
+        name.starts_with("GLUE_EMBER_ZCL_STATUS_")
+            .then_some(bindgen::callbacks::IntKind::Custom {
+                name: "glue_EmberAfStatus",
+                is_signed: false,
+            })
+    }
+}
+
+/// Generate the plain C `glue_config.h` with the build configuration the Rust code needs,
+/// by running the C++ preprocessor with the include paths of CHIPALL on `lib/glue_config.h.in`
+fn gen_glue_config(includes: &[impl AsRef<Path>], out_dir: &Path) -> Result<PathBuf> {
+    let template = "lib/glue_config.h.in";
+
+    println!("cargo:rerun-if-changed={template}");
+    println!("cargo:rerun-if-env-changed=CXX");
+
+    let cxx = env::var("CXX").unwrap_or_else(|_| "c++".to_owned());
+
+    let incl_args = includes
+        .iter()
+        .map(|include| format!("-I{}", include.as_ref().display()))
+        .collect::<Vec<_>>();
+
+    let preprocessed = cmd!(cxx, "-E", "-P", "-x", "c++", "-std=gnu++17", @incl_args, template)
+        .stdout()
+        .map_err(|err| anyhow!("Cannot preprocess {template}: {err}"))?;
+
+    let mut glue_config = format!(
+        "#pragma once\n\n// Generated from `{template}` by the `chip-sys` build script\n\n"
+    );
+
+    for config in preprocessed
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("glue_config "))
+    {
+        let (name, value) = config
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .ok_or_else(|| anyhow!("Malformed line `{config}` in {template}"))?;
+
+        // An undefined macro is left as is by the preprocessor
+        if value.is_empty() || value.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            anyhow::bail!("`GLUE_CONFIG_{name}` has no value in {template}: `{value}`");
+        }
+
+        glue_config.push_str(&format!("#define GLUE_CONFIG_{name} {value}\n"));
+    }
+
+    let glue_config_file = out_dir.join("glue_config.h");
+    fs::write(&glue_config_file, glue_config)?;
+
+    Ok(glue_config_file)
+}
+
+/// Get the comma-separated list of values of the `var` environment variable
+fn get_list(var: &str) -> Vec<String> {
+    println!("cargo:rerun-if-env-changed={var}");
+
+    env::var(var)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Get the boolean value of the `var` environment variable, `false` if not set
+fn get_bool(var: &str) -> Result<bool> {
+    println!("cargo:rerun-if-env-changed={var}");
+
+    match env::var(var)
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+        .as_str()
+    {
+        "" | "0" | "false" | "no" | "off" => Ok(false),
+        "1" | "true" | "yes" | "on" => Ok(true),
+        other => anyhow::bail!("`{var}`: expected a boolean, got `{other}`"),
+    }
+}
+
 fn get_chip() -> Result<sdk::SdkOrigin> {
     let sdk = if let Ok(sdk) = std::env::var(CHIP_PATH) {
         sdk::SdkOrigin::Custom(git::Repository::new(PathBuf::from(sdk)))
@@ -284,7 +1371,232 @@ fn get_chip() -> Result<sdk::SdkOrigin> {
     Ok(sdk)
 }
 
-fn get_chip_includes(sdk: &git::Repository, chip_out_dir: &Path) -> Result<Vec<PathBuf>> {
+/// The ZAP data model (fixed endpoints and compiled-in clusters) CHIPALL is built against
+struct DataModel {
+    /// The `.zap` file passed to `chip_data_model`
+    zap_file: PathBuf,
+    /// The `.matter` IDL of the data model; always next to `zap_file`
+    matter_file: PathBuf,
+    /// The directory containing the `zap-generated` sources of the data model
+    zap_dir: PathBuf,
+    /// Whether the `zap-generated` sources need to be generated by the ZAP tool first
+    codegen: bool,
+    /// Fixed endpoints which are only there so that ZAP generates the code of the clusters
+    /// used by the dynamic endpoints; these are disabled during initialization
+    template_endpoints: Vec<u16>,
+    /// The server clusters left enabled, if the data model is trimmed with `CHIP_SERVER_CLUSTERS`
+    server_clusters: Option<Vec<u32>>,
+}
+
+impl DataModel {
+    fn zap_generated_dir(&self) -> PathBuf {
+        self.zap_dir.join("zap-generated")
+    }
+
+    /// The file name of the `.matter` IDL, for the header of the code generated from it
+    fn idl_source(&self) -> String {
+        self.matter_file
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+fn get_data_model(sdk: &git::Repository, chip_out_dir: &Path) -> Result<DataModel> {
+    println!("cargo:rerun-if-env-changed={CHIP_ZAP_FILE}");
+
+    let model = if let Ok(zap_file) = env::var(CHIP_ZAP_FILE) {
+        let zap_file = workspace_dir().unwrap().join(zap_file).canonicalize()?;
+        let matter_file = zap_file.with_extension("matter");
+
+        println!("cargo:rerun-if-changed={}", zap_file.display());
+
+        if matter_file.exists() {
+            println!("cargo:rerun-if-changed={}", matter_file.display());
+        }
+
+        copy_data_model(&zap_file, chip_out_dir)?
+    } else {
+        let sdk = sdk.worktree().canonicalize()?;
+
+        let zap_file = sdk.join(BRIDGE_APP_ZAP_FILE);
+
+        DataModel {
+            matter_file: zap_file.with_extension("matter"),
+            zap_file,
+            zap_dir: sdk.join(BRIDGE_APP_ZAP_DIR),
+            codegen: false,
+            template_endpoints: BRIDGE_APP_TEMPLATE_ENDPOINTS.to_vec(),
+            server_clusters: None,
+        }
+    };
+
+    let template_endpoints = get_list(CHIP_TEMPLATE_ENDPOINTS);
+
+    let model = if env::var(CHIP_TEMPLATE_ENDPOINTS).is_ok() {
+        DataModel {
+            template_endpoints: template_endpoints
+                .iter()
+                .map(|endpoint| {
+                    endpoint.parse::<u16>().map_err(|_| {
+                        anyhow!("Invalid endpoint `{endpoint}` in `{CHIP_TEMPLATE_ENDPOINTS}`")
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?,
+            ..model
+        }
+    } else {
+        model
+    };
+
+    let model = if env::var(CHIP_SERVER_CLUSTERS).is_ok() {
+        let model = if model.codegen {
+            model
+        } else {
+            DataModel {
+                template_endpoints: model.template_endpoints.clone(),
+                ..copy_data_model(&model.zap_file, chip_out_dir)?
+            }
+        };
+
+        DataModel {
+            server_clusters: Some(trim_data_model(&model)?),
+            ..model
+        }
+    } else {
+        model
+    };
+
+    Ok(model)
+}
+
+/// Copy a data model into the out dir, so that its sources are generated there during the build
+fn copy_data_model(zap_file: &Path, chip_out_dir: &Path) -> Result<DataModel> {
+    // `chip_data_model` expects the `.matter` IDL next to the `.zap` file, and the
+    // ZAP codegen (re)generates it there, so work on a copy in the out dir
+    let zap_dir = chip_out_dir.join("zap");
+
+    let model_zap_file = model::copy_zap_file(zap_file, &zap_dir)?;
+
+    Ok(DataModel {
+        matter_file: model_zap_file.with_extension("matter"),
+        zap_file: model_zap_file,
+        zap_dir,
+        codegen: true,
+        template_endpoints: Vec::new(),
+        server_clusters: None,
+    })
+}
+
+/// Disable the server clusters of the data model which are neither in `CHIP_SERVER_CLUSTERS`,
+/// nor in `REQUIRED_SERVER_CLUSTERS`, so that `chip_data_model` does not compile their implementations.
+/// Returns the server clusters left enabled
+fn trim_data_model(model: &DataModel) -> Result<Vec<u32>> {
+    let idl = if model.matter_file.exists() {
+        Some(get_idl(model)?)
+    } else {
+        None
+    };
+
+    let mut server_clusters = REQUIRED_SERVER_CLUSTERS.to_vec();
+
+    for cluster in get_list(CHIP_SERVER_CLUSTERS) {
+        let id = if let Some(hex) = cluster.strip_prefix("0x") {
+            u32::from_str_radix(hex, 16).ok()
+        } else {
+            cluster.parse().ok()
+        };
+
+        let id = match (id, &idl) {
+            (Some(id), _) => id,
+            (None, Some(idl)) => idl
+                .cluster(ClusterSide::Server, &cluster)
+                .map(|cluster| cluster.code)
+                .ok_or_else(|| {
+                    anyhow!("`{CHIP_SERVER_CLUSTERS}`: the data model has no `{cluster}` server cluster")
+                })?,
+            (None, None) => anyhow::bail!(
+                "`{CHIP_SERVER_CLUSTERS}`: the data model has no `.matter` IDL to look up `{cluster}` in, use its cluster ID"
+            ),
+        };
+
+        server_clusters.push(id);
+    }
+
+    let mut zap = model::read_zap_file(&model.zap_file)?;
+
+    let enabled = model::trim_server_clusters(&mut zap, &server_clusters)
+        .map_err(|err| anyhow!("{}: {err}", model.zap_file.display()))?;
+
+    for id in server_clusters {
+        if !enabled.contains(&id) && !REQUIRED_SERVER_CLUSTERS.contains(&id) {
+            println!("cargo:warning=`{CHIP_SERVER_CLUSTERS}`: cluster {id:#06x} is not a server cluster of the data model");
+        }
+    }
+
+    model::write_zap_file(&model.zap_file, &zap)?;
+
+    Ok(enabled)
+}
+
+fn get_idl(model: &DataModel) -> Result<Idl> {
+    let matter = fs::read_to_string(&model.matter_file)?;
+
+    let mut idl =
+        Idl::parse(&matter).map_err(|err| anyhow!("{}: {err}", model.matter_file.display()))?;
+
+    // Depending on its templates, the ZAP tool might not regenerate the `.matter` IDL from the
+    // trimmed data model, so drop the server clusters trimming disabled from whichever IDL is
+    // there, for the Rust code generated from it to match the compiled-in clusters
+    if let Some(server_clusters) = &model.server_clusters {
+        model::filter_idl(&mut idl, server_clusters);
+    }
+
+    Ok(idl)
+}
+
+fn gen_endpoints(model: &DataModel, idl: &Idl, out_dir: &Path) -> Result<()> {
+    let endpoints_file = out_dir.join("endpoints.rs");
+
+    fs::write(
+        &endpoints_file,
+        codegen::endpoints(idl, &model.idl_source(), &model.template_endpoints)?,
+    )?;
+
+    if !codegen::has_root_node(idl) {
+        println!(
+            "cargo:warning=Endpoint 0 of {} does not have the root node device type",
+            model.matter_file.display()
+        );
+    }
+
+    println!(
+        "cargo:rustc-env=GENERATED_ENDPOINTS_FILE={}",
+        endpoints_file.display()
+    );
+
+    Ok(())
+}
+
+fn gen_clusters(model: &DataModel, idl: &Idl, out_dir: &Path) -> Result<()> {
+    let clusters_file = out_dir.join("clusters.rs");
+
+    fs::write(&clusters_file, codegen::clusters(idl, &model.idl_source())?)?;
+
+    println!(
+        "cargo:rustc-env=GENERATED_CLUSTERS_FILE={}",
+        clusters_file.display()
+    );
+
+    Ok(())
+}
+
+fn get_chip_includes(
+    sdk: &git::Repository,
+    model: &DataModel,
+    chip_out_dir: &Path,
+) -> Result<Vec<PathBuf>> {
     let sdk = sdk.worktree().canonicalize()?;
 
     let third_party = sdk.join("third_party");
@@ -302,7 +1614,7 @@ fn get_chip_includes(sdk: &git::Repository, chip_out_dir: &Path) -> Result<Vec<P
         #[cfg(target_os = "espidf")]
         sdk.join("config/esp32"),
         // Generated ZAP includes
-        sdk.join("zzz_generated/bridge-app"),
+        model.zap_dir.clone(),
         sdk.join("zzz_generated/app-common"),
         // SDK
         sdk.join("src/include"),
@@ -314,6 +1626,7 @@ fn get_chip_includes(sdk: &git::Repository, chip_out_dir: &Path) -> Result<Vec<P
         third_party.join("inipp/repo/inipp"),
     ]
     .into_iter()
+    .chain(get_extra_sources()?.include_dirs)
     .chain(
         get_pkg_libs(false)?
             .into_iter()
@@ -324,7 +1637,7 @@ fn get_chip_includes(sdk: &git::Repository, chip_out_dir: &Path) -> Result<Vec<P
     Ok(includes)
 }
 
-fn get_chip_libs(_sdk: &git::Repository, _chip_out_dir: &Path) -> Result<Vec<String>> {
+fn get_chip_libs() -> Result<Vec<String>> {
     let libs = iter::once("CHIPALL".to_owned())
         .chain(iter::once("stdc++".to_owned()))
         .chain(iter::once("crypto".to_owned()))
@@ -338,7 +1651,7 @@ fn get_chip_libs(_sdk: &git::Repository, _chip_out_dir: &Path) -> Result<Vec<Str
     Ok(libs)
 }
 
-fn get_chip_lib_paths(_sdk: &git::Repository, chip_out_dir: &Path) -> Result<Vec<PathBuf>> {
+fn get_chip_lib_paths(chip_out_dir: &Path) -> Result<Vec<PathBuf>> {
     let libp = iter::once(chip_out_dir.to_owned())
         .chain(
             get_pkg_libs(false)?
@@ -378,56 +1691,103 @@ fn get_pkg_libs(emit_cargo_metadata: bool) -> Result<Vec<Library>> {
     Ok(libs)
 }
 
-fn create_app_config(dir: &Path) -> Result<()> {
+fn get_app_config() -> Result<AppConfig> {
+    fn get_num(var: &str) -> Result<Option<u32>> {
+        println!("cargo:rerun-if-env-changed={var}");
+
+        env::var(var)
+            .ok()
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<u32>()
+                    .map_err(|_| anyhow!("Invalid value `{value}` of `{var}`: not a number"))
+            })
+            .transpose()
+    }
+
     #[allow(unused_mut, unused_assignments)]
-    let mut arg_dynamic_endpoint_count = 4;
+    let mut dynamic_endpoint_count = 4;
 
     #[cfg(feature = "endpoints-8")]
     {
-        arg_dynamic_endpoint_count = 8;
+        dynamic_endpoint_count = 8;
     }
     #[cfg(feature = "endpoints-16")]
     {
-        arg_dynamic_endpoint_count = 16;
+        dynamic_endpoint_count = 16;
     }
     #[cfg(feature = "endpoints-32")]
     {
-        arg_dynamic_endpoint_count = 32;
+        dynamic_endpoint_count = 32;
     }
     #[cfg(feature = "endpoints-64")]
     {
-        arg_dynamic_endpoint_count = 64;
+        dynamic_endpoint_count = 64;
     }
     #[cfg(feature = "endpoints-128")]
     {
-        arg_dynamic_endpoint_count = 128;
+        dynamic_endpoint_count = 128;
     }
     #[cfg(feature = "endpoints-256")]
     {
-        arg_dynamic_endpoint_count = 256;
+        dynamic_endpoint_count = 256;
     }
     #[cfg(feature = "endpoints-512")]
     {
-        arg_dynamic_endpoint_count = 512;
+        dynamic_endpoint_count = 512;
     }
     #[cfg(feature = "endpoints-1024")]
     {
-        arg_dynamic_endpoint_count = 1024;
+        dynamic_endpoint_count = 1024;
     }
 
+    println!("cargo:rerun-if-env-changed={CHIP_KVS_PATH}");
+    println!("cargo:rerun-if-env-changed={CHIP_APP_CONFIG}");
+
+    let defines = config::parse_defines(&env::var(CHIP_APP_CONFIG).unwrap_or_default())
+        .map_err(|err| anyhow!("`{CHIP_APP_CONFIG}`: {err}"))?;
+
+    let kvs_path = env::var(CHIP_KVS_PATH).ok();
+
+    if let Some(kvs_path) = &kvs_path {
+        // Not a numeric define, hence not picked up by bindgen
+        println!("cargo:rustc-env=CHIP_CONFIG_KVS_PATH={kvs_path}");
+    }
+
+    let app_config = AppConfig {
+        dynamic_endpoint_count: get_num(CHIP_DYNAMIC_ENDPOINT_COUNT)?
+            .unwrap_or(dynamic_endpoint_count),
+        max_fabrics: get_num(CHIP_MAX_FABRICS)?,
+        secure_session_pool_size: get_num(CHIP_SECURE_SESSION_POOL_SIZE)?,
+        subscriptions_per_fabric: get_num(CHIP_SUBSCRIPTIONS_PER_FABRIC)?,
+        max_exchange_contexts: get_num(CHIP_MAX_EXCHANGE_CONTEXTS)?,
+        kvs_path,
+        defines,
+    };
+
+    app_config
+        .check()
+        .map_err(|err| anyhow!("`{CHIP_APP_CONFIG}`: {err}"))?;
+
+    Ok(app_config)
+}
+
+fn create_app_config(dir: &Path, app_config: &AppConfig) -> Result<()> {
+    let config = app_config.header();
+
     fs::create_dir_all(dir)?;
 
-    let mut file = File::create(dir.join("CHIPProjectAppConfig.h"))?;
+    let config_file = dir.join("CHIPProjectAppConfig.h");
 
-    write!(
-        &mut file,
-        "#pragma once
-#define CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT {arg_dynamic_endpoint_count}
-#include <CHIPProjectConfig.h>
-"
-    )?;
+    // Only touch the header when the configuration actually changed,
+    // so that ninja does not rebuild the whole SDK on every build
+    if fs::read_to_string(&config_file).ok().as_deref() != Some(config.as_str()) {
+        let mut file = File::create(config_file)?;
 
-    file.flush()?;
+        file.write_all(config.as_bytes())?;
+        file.flush()?;
+    }
 
     Ok(())
 }
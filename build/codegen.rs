@@ -0,0 +1,431 @@
+//! The Rust code generated from the `.matter` IDL of the data model: the fixed endpoints
+//! (`endpoints.rs`, included by the `chip` module) and the `clusters` module (`clusters.rs`).
+//!
+//! Used by the build script, and kept free of its environment (cargo variables and SDK checkout)
+//! so that it can be unit-tested.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::idl::{ClusterSide, Idl};
+
+pub const ROOT_NODE_DEVICE_TYPE: u32 = 0x0016;
+pub const AGGREGATOR_DEVICE_TYPE: u32 = 0x000e;
+
+/// Whether endpoint 0 of `idl` has the root node device type, as the SDK expects
+pub fn has_root_node(idl: &Idl) -> bool {
+    idl.endpoint(0).is_some_and(|endpoint| {
+        endpoint
+            .device_types
+            .iter()
+            .any(|device_type| device_type.code == ROOT_NODE_DEVICE_TYPE)
+    })
+}
+
+/// The fixed endpoints of `idl`, generated from the IDL file named `source`
+pub fn endpoints(idl: &Idl, source: &str, template_endpoints: &[u16]) -> Result<String> {
+    let endpoints = &idl.endpoints;
+
+    let bridge = endpoints
+        .iter()
+        .find(|endpoint| {
+            endpoint
+                .device_types
+                .iter()
+                .any(|device_type| device_type.code == AGGREGATOR_DEVICE_TYPE)
+        })
+        .map(|endpoint| endpoint.number);
+
+    let mut out = String::new();
+
+    writeln!(out, "// Generated from {source}; do not edit\n")?;
+
+    for endpoint in endpoints {
+        let id = endpoint.number;
+
+        let name = if id == 0 {
+            "ROOT_NODE".to_owned()
+        } else if Some(id) == bridge {
+            "BRIDGE_NODE".to_owned()
+        } else {
+            format!("ENDPOINT_{id}")
+        };
+
+        writeln!(
+            out,
+            "pub const {name}: StaticEndpoint<{id}> = StaticEndpoint;"
+        )?;
+    }
+
+    writeln!(
+        out,
+        "\npub const FIXED_ENDPOINT_COUNT: u16 = {};",
+        endpoints.len()
+    )?;
+
+    writeln!(
+        out,
+        "\nstatic FIXED_ENDPOINTS: &[(glue_EndpointId, DeviceTypes<'static>)] = &["
+    )?;
+
+    for endpoint in endpoints
+        .iter()
+        .filter(|endpoint| !template_endpoints.contains(&endpoint.number))
+    {
+        let device_types = endpoint
+            .device_types
+            .iter()
+            .map(|device_type| {
+                Ok(format!(
+                    "DeviceType::new({:#06x}, {})",
+                    u16::try_from(device_type.code)?,
+                    u8::try_from(device_type.version)?
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join(", ");
+
+        writeln!(out, "    ({}, &[{device_types}]),", endpoint.number)?;
+    }
+
+    writeln!(out, "];")?;
+
+    let template_endpoints = template_endpoints
+        .iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    writeln!(
+        out,
+        "\nstatic TEMPLATE_ENDPOINTS: &[glue_EndpointId] = &[{template_endpoints}];"
+    )?;
+
+    let bridge_endpoints = bridge.map(|id| id.to_string()).unwrap_or_default();
+
+    writeln!(
+        out,
+        "\nstatic BRIDGE_ENDPOINTS: &[glue_EndpointId] = &[{bridge_endpoints}];"
+    )?;
+
+    Ok(out)
+}
+
+/// The `clusters` module of `idl`, generated from the IDL file named `source`
+pub fn clusters(idl: &Idl, source: &str) -> Result<String> {
+    let mut clusters = idl
+        .clusters
+        .iter()
+        .filter(|cluster| cluster.side == ClusterSide::Server)
+        .collect::<Vec<_>>();
+
+    // Clusters the data model only has a client for
+    clusters.extend(idl.clusters.iter().filter(|cluster| {
+        cluster.side == ClusterSide::Client
+            && idl
+                .cluster_by_code(ClusterSide::Server, cluster.code)
+                .is_none()
+    }));
+
+    clusters.sort_by_key(|cluster| cluster.code);
+
+    let mut out = String::new();
+
+    writeln!(out, "// Generated from {source}; do not edit")?;
+
+    for cluster in &clusters {
+        writeln!(out, "\n/// The `{}` cluster", cluster.name)?;
+        writeln!(out, "pub mod {} {{", snake_case(&cluster.name))?;
+        writeln!(
+            out,
+            "    pub const ID: crate::clusters::ClusterId = crate::clusters::ClusterId({:#06x});",
+            cluster.code
+        )?;
+
+        let attributes = cluster
+            .attributes
+            .iter()
+            .map(|attribute| (&attribute.definition.name, attribute.definition.code))
+            .collect::<Vec<_>>();
+        let commands = cluster
+            .commands
+            .iter()
+            .map(|command| (&command.name, command.code))
+            .collect::<Vec<_>>();
+        let events = cluster
+            .events
+            .iter()
+            .map(|event| (&event.name, event.code))
+            .collect::<Vec<_>>();
+
+        let ids = [
+            ("attributes", "AttributeId", attributes),
+            ("commands", "CommandId", commands),
+            ("events", "EventId", events),
+        ];
+
+        for (module, typ, items) in ids {
+            writeln!(out, "\n    pub mod {module} {{")?;
+
+            for (name, id) in items {
+                writeln!(
+                    out,
+                    "        pub const {}: crate::clusters::{typ} = crate::clusters::{typ}({id:#06x});",
+                    snake_case(name).to_uppercase()
+                )?;
+            }
+
+            writeln!(out, "    }}")?;
+        }
+
+        writeln!(out, "\n    pub mod enums {{")?;
+
+        for enum_decl in &cluster.enums {
+            let repr = match enum_decl.base_type.as_str() {
+                "ENUM16" => "u16",
+                _ => "u8",
+            };
+
+            let mut variants: Vec<(String, u64)> = Vec::new();
+
+            for entry in &enum_decl.entries {
+                let variant = enum_variant(&entry.name);
+
+                if !variants.iter().any(|(other, _)| *other == variant) {
+                    variants.push((variant, entry.code));
+                }
+            }
+
+            // `#[repr]` is not allowed on an enum without variants, which would be of no use anyway
+            if variants.is_empty() {
+                continue;
+            }
+
+            writeln!(
+                out,
+                "\n        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]\n        #[repr({repr})]\n        pub enum {} {{",
+                enum_decl.name
+            )?;
+
+            for (variant, value) in &variants {
+                writeln!(out, "            {variant} = {value},")?;
+            }
+
+            writeln!(out, "        }}")?;
+
+            writeln!(
+                out,
+                "\n        impl From<{0}> for {repr} {{\n            fn from(value: {0}) -> Self {{\n                value as _\n            }}\n        }}",
+                enum_decl.name
+            )?;
+
+            writeln!(
+                out,
+                "\n        impl TryFrom<{repr}> for {} {{\n            type Error = {repr};\n\n            fn try_from(value: {repr}) -> Result<Self, Self::Error> {{\n                match value {{",
+                enum_decl.name
+            )?;
+
+            for (variant, value) in &variants {
+                writeln!(out, "                    {value} => Ok(Self::{variant}),")?;
+            }
+
+            writeln!(
+                out,
+                "                    _ => Err(value),\n                }}\n            }}\n        }}"
+            )?;
+        }
+
+        writeln!(out, "    }}\n}}")?;
+    }
+
+    Ok(out)
+}
+
+/// `OnOff` -> `on_off`, `WiFiNetworkDiagnostics` -> `wi_fi_network_diagnostics`
+pub fn snake_case(name: &str) -> String {
+    let chars = name.chars().collect::<Vec<_>>();
+    let mut result = String::new();
+
+    for (index, ch) in chars.iter().enumerate() {
+        if ch.is_uppercase() && index > 0 {
+            let prev = chars[index - 1];
+            let next_lower = chars.get(index + 1).map(|next| next.is_lowercase()) == Some(true);
+
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                result.push('_');
+            }
+        }
+
+        result.extend(ch.to_lowercase());
+    }
+
+    result
+}
+
+/// `kFadeToOffIn0p8Seconds` -> `FadeToOffIn0p8Seconds`, `k50Percent` -> `K50Percent`
+pub fn enum_variant(name: &str) -> String {
+    let name = match name.strip_prefix('k') {
+        Some(rest) if rest.starts_with(|ch: char| ch.is_uppercase()) => rest,
+        _ => name,
+    };
+
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = r#"
+        client cluster OnOff = 6 {
+          command Off(): DefaultSuccess = 0;
+        }
+
+        server cluster LevelControl = 8 {
+          enum MoveMode : ENUM8 {
+            kUp = 0;
+            kDown = 1;
+          }
+
+          enum StepMode : ENUM16 {
+            kUp = 0;
+            up = 2;
+            kDown = 1;
+          }
+
+          enum Reserved : ENUM8 {
+          }
+
+          readonly attribute int8u currentLevel = 0;
+          readonly attribute int16u clusterRevision = 65533;
+
+          command MoveToLevel(): DefaultSuccess = 0;
+        }
+
+        server cluster BridgedDeviceBasic = 57 {
+          readonly attribute boolean reachable = 17;
+        }
+
+        client cluster BridgedDeviceBasic = 57 {
+          readonly attribute boolean reachable = 17;
+        }
+
+        endpoint 0 {
+          device type rootdevice = 22, version 1;
+        }
+        endpoint 1 {
+          device type bridge = 14, version 1;
+        }
+        endpoint 2 {
+          device type dimmablelight = 0x0101, version 2;
+          device type bridgednode = 0x0013, version 1;
+        }
+    "#;
+
+    #[test]
+    fn names() {
+        assert_eq!(snake_case("OnOff"), "on_off");
+        assert_eq!(
+            snake_case("WiFiNetworkDiagnostics"),
+            "wi_fi_network_diagnostics"
+        );
+        assert_eq!(snake_case("ACLEntry"), "acl_entry");
+        assert_eq!(snake_case("nodeLabel"), "node_label");
+        assert_eq!(snake_case("Level2Control"), "level2_control");
+        assert_eq!(snake_case("clusterRevision"), "cluster_revision");
+
+        assert_eq!(
+            enum_variant("kFadeToOffIn0p8Seconds"),
+            "FadeToOffIn0p8Seconds"
+        );
+        assert_eq!(enum_variant("k50Percent"), "K50Percent");
+        assert_eq!(enum_variant("kernel"), "Kernel");
+        assert_eq!(enum_variant("Up"), "Up");
+        assert_eq!(enum_variant(""), "");
+    }
+
+    #[test]
+    fn endpoints() {
+        let idl = Idl::parse(MODEL).unwrap();
+
+        assert!(has_root_node(&idl));
+        assert!(!has_root_node(&Idl::parse("endpoint 0 {}").unwrap()));
+
+        assert_eq!(
+            super::endpoints(&idl, "model.matter", &[2]).unwrap(),
+            "// Generated from model.matter; do not edit\n\
+             \n\
+             pub const ROOT_NODE: StaticEndpoint<0> = StaticEndpoint;\n\
+             pub const BRIDGE_NODE: StaticEndpoint<1> = StaticEndpoint;\n\
+             pub const ENDPOINT_2: StaticEndpoint<2> = StaticEndpoint;\n\
+             \n\
+             pub const FIXED_ENDPOINT_COUNT: u16 = 3;\n\
+             \n\
+             static FIXED_ENDPOINTS: &[(glue_EndpointId, DeviceTypes<'static>)] = &[\n    \
+                 (0, &[DeviceType::new(0x0016, 1)]),\n    \
+                 (1, &[DeviceType::new(0x000e, 1)]),\n\
+             ];\n\
+             \n\
+             static TEMPLATE_ENDPOINTS: &[glue_EndpointId] = &[2];\n\
+             \n\
+             static BRIDGE_ENDPOINTS: &[glue_EndpointId] = &[1];\n"
+        );
+
+        let endpoints = super::endpoints(&idl, "model.matter", &[]).unwrap();
+
+        assert!(endpoints
+            .contains("    (2, &[DeviceType::new(0x0101, 2), DeviceType::new(0x0013, 1)]),\n"));
+        assert!(endpoints.contains("TEMPLATE_ENDPOINTS: &[glue_EndpointId] = &[];"));
+
+        assert!(
+            super::endpoints(&Idl::parse("").unwrap(), "empty.matter", &[])
+                .unwrap()
+                .contains("BRIDGE_ENDPOINTS: &[glue_EndpointId] = &[];")
+        );
+    }
+
+    #[test]
+    fn clusters() {
+        let clusters = super::clusters(&Idl::parse(MODEL).unwrap(), "model.matter").unwrap();
+
+        // Sorted by ID, with the client-only clusters, and each cluster only once
+        let modules = clusters
+            .lines()
+            .filter_map(|line| line.strip_prefix("pub mod "))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            modules,
+            ["on_off {", "level_control {", "bridged_device_basic {"]
+        );
+
+        assert!(clusters.contains(
+            "    pub const ID: crate::clusters::ClusterId = crate::clusters::ClusterId(0x0008);\n"
+        ));
+        assert!(clusters.contains(
+            "        pub const CLUSTER_REVISION: crate::clusters::AttributeId = crate::clusters::AttributeId(0xfffd);\n"
+        ));
+        assert!(clusters.contains(
+            "        pub const MOVE_TO_LEVEL: crate::clusters::CommandId = crate::clusters::CommandId(0x0000);\n"
+        ));
+
+        assert!(clusters.contains(
+            "        #[repr(u8)]\n        pub enum MoveMode {\n            Up = 0,\n            Down = 1,\n        }\n"
+        ));
+
+        // Entries mapping to the same variant are only generated once
+        assert!(clusters.contains(
+            "        #[repr(u16)]\n        pub enum StepMode {\n            Up = 0,\n            Down = 1,\n        }\n"
+        ));
+        assert!(clusters.contains("impl TryFrom<u16> for StepMode {"));
+        assert!(clusters.contains("                    1 => Ok(Self::Down),\n"));
+
+        // Enums without entries cannot have a `#[repr]`
+        assert!(!clusters.contains("Reserved"));
+    }
+}
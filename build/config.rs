@@ -0,0 +1,264 @@
+//! The generated `CHIPProjectAppConfig.h` of the CHIPALL build.
+//!
+//! Used by the build script, and kept free of its environment (cargo variables and SDK checkout)
+//! so that it can be unit-tested.
+
+use anyhow::{anyhow, Result};
+
+/// The tuning knobs of the generated `CHIPProjectAppConfig.h`
+///
+/// Knobs which are not set keep the defaults of the SDK and the platform.
+pub struct AppConfig {
+    pub dynamic_endpoint_count: u32,
+    pub max_fabrics: Option<u32>,
+    pub secure_session_pool_size: Option<u32>,
+    pub subscriptions_per_fabric: Option<u32>,
+    pub max_exchange_contexts: Option<u32>,
+    pub kvs_path: Option<String>,
+    /// Additional `#define`s, as `(name, value)` pairs
+    pub defines: Vec<(String, String)>,
+}
+
+impl AppConfig {
+    /// Check that the additional defines neither repeat each other, nor redefine the knobs
+    pub fn check(&self) -> Result<()> {
+        let knobs = self.knobs();
+
+        for (index, (name, _)) in self.defines.iter().enumerate() {
+            if let Some((_, _, var)) = knobs.iter().find(|(knob, _, _)| knob == name) {
+                anyhow::bail!("`{name}` is already defined from `{var}`");
+            }
+
+            if self.defines[..index].iter().any(|(other, _)| other == name) {
+                anyhow::bail!("`{name}` is defined more than once");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The contents of `CHIPProjectAppConfig.h`
+    pub fn header(&self) -> String {
+        let knobs = self
+            .knobs()
+            .into_iter()
+            .map(|(name, value, _)| (name.to_owned(), value));
+
+        let defines = self
+            .defines
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()));
+
+        let mut config = "#pragma once\n".to_owned();
+
+        for (name, value) in knobs.chain(defines) {
+            config.push_str(&format!("#define {name} {value}\n"));
+        }
+
+        config.push_str("#include <CHIPProjectConfig.h>\n");
+
+        config
+    }
+
+    /// The defines of the knobs which are set, as `(name, value, variable setting it)`
+    fn knobs(&self) -> Vec<(&'static str, String, &'static str)> {
+        let mut knobs = vec![(
+            "CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT",
+            self.dynamic_endpoint_count.to_string(),
+            "CHIP_DYNAMIC_ENDPOINT_COUNT",
+        )];
+
+        if let Some(max_fabrics) = self.max_fabrics {
+            knobs.push((
+                "CHIP_CONFIG_MAX_FABRICS",
+                max_fabrics.to_string(),
+                "CHIP_MAX_FABRICS",
+            ));
+        }
+
+        if let Some(pool_size) = self.secure_session_pool_size {
+            knobs.push((
+                "CHIP_CONFIG_SECURE_SESSION_POOL_SIZE",
+                pool_size.to_string(),
+                "CHIP_SECURE_SESSION_POOL_SIZE",
+            ));
+        }
+
+        if let Some(subscriptions) = self.subscriptions_per_fabric {
+            knobs.push((
+                "CHIP_IM_MAX_NUM_SUBSCRIPTIONS",
+                format!("(CHIP_CONFIG_MAX_FABRICS * {subscriptions})"),
+                "CHIP_SUBSCRIPTIONS_PER_FABRIC",
+            ));
+        }
+
+        if let Some(exchange_contexts) = self.max_exchange_contexts {
+            knobs.push((
+                "CHIP_CONFIG_MAX_EXCHANGE_CONTEXTS",
+                exchange_contexts.to_string(),
+                "CHIP_MAX_EXCHANGE_CONTEXTS",
+            ));
+        }
+
+        if let Some(kvs_path) = &self.kvs_path {
+            knobs.push((
+                "CHIP_CONFIG_KVS_PATH",
+                c_string_literal(kvs_path),
+                "CHIP_KVS_PATH",
+            ));
+        }
+
+        knobs
+    }
+}
+
+/// Parse `;`-separated `NAME=VALUE` defines
+pub fn parse_defines(defines: &str) -> Result<Vec<(String, String)>> {
+    defines
+        .split(';')
+        .map(str::trim)
+        .filter(|define| !define.is_empty())
+        .map(|define| {
+            let (name, value) = define
+                .split_once('=')
+                .map(|(name, value)| (name.trim(), value.trim()))
+                .ok_or_else(|| anyhow!("Invalid define `{define}`: expected `NAME=VALUE`"))?;
+
+            let identifier = name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+            if !identifier {
+                anyhow::bail!("Invalid define `{define}`: `{name}` is not a macro name");
+            }
+
+            Ok((name.to_owned(), value.to_owned()))
+        })
+        .collect()
+}
+
+/// Quote `value` as a C string literal.
+///
+/// Anything but printable ASCII is escaped byte by byte in octal, as octal escapes - unlike
+/// hex ones - cannot run into the characters following them; `?` is escaped against trigraphs.
+pub fn c_string_literal(value: &str) -> String {
+    let mut literal = "\"".to_owned();
+
+    for byte in value.bytes() {
+        match byte {
+            b'"' | b'\\' | b'?' => {
+                literal.push('\\');
+                literal.push(byte as char);
+            }
+            b' '..=b'~' => literal.push(byte as char),
+            _ => literal.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+
+    literal.push('"');
+
+    literal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_config(defines: &str) -> AppConfig {
+        AppConfig {
+            dynamic_endpoint_count: 16,
+            max_fabrics: Some(5),
+            secure_session_pool_size: None,
+            subscriptions_per_fabric: Some(2),
+            max_exchange_contexts: None,
+            kvs_path: None,
+            defines: parse_defines(defines).unwrap(),
+        }
+    }
+
+    #[test]
+    fn defines() {
+        assert_eq!(
+            parse_defines(" CHIP_CONFIG_A = 1;;CHIP_CONFIG_B=(2 * 3); _C= ;").unwrap(),
+            [
+                ("CHIP_CONFIG_A".to_owned(), "1".to_owned()),
+                ("CHIP_CONFIG_B".to_owned(), "(2 * 3)".to_owned()),
+                ("_C".to_owned(), String::new()),
+            ]
+        );
+
+        assert!(parse_defines("").unwrap().is_empty());
+
+        assert!(parse_defines("CHIP_CONFIG_A")
+            .unwrap_err()
+            .to_string()
+            .contains("expected `NAME=VALUE`"));
+        assert!(parse_defines("1ABC=1").is_err());
+        assert!(parse_defines("A B=1").is_err());
+        assert!(parse_defines("=1").is_err());
+    }
+
+    #[test]
+    fn header() {
+        let config = AppConfig {
+            kvs_path: Some("/tmp/chip kvs".to_owned()),
+            ..app_config("CHIP_CONFIG_EXTRA=1")
+        };
+
+        config.check().unwrap();
+
+        assert_eq!(
+            config.header(),
+            "#pragma once\n\
+             #define CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT 16\n\
+             #define CHIP_CONFIG_MAX_FABRICS 5\n\
+             #define CHIP_IM_MAX_NUM_SUBSCRIPTIONS (CHIP_CONFIG_MAX_FABRICS * 2)\n\
+             #define CHIP_CONFIG_KVS_PATH \"/tmp/chip kvs\"\n\
+             #define CHIP_CONFIG_EXTRA 1\n\
+             #include <CHIPProjectConfig.h>\n"
+        );
+    }
+
+    #[test]
+    fn redefinitions() {
+        assert_eq!(
+            app_config("CHIP_CONFIG_MAX_FABRICS=8")
+                .check()
+                .unwrap_err()
+                .to_string(),
+            "`CHIP_CONFIG_MAX_FABRICS` is already defined from `CHIP_MAX_FABRICS`"
+        );
+
+        assert!(app_config("CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT=8")
+            .check()
+            .is_err());
+
+        assert_eq!(
+            app_config("CHIP_CONFIG_A=1;CHIP_CONFIG_B=2;CHIP_CONFIG_A=3")
+                .check()
+                .unwrap_err()
+                .to_string(),
+            "`CHIP_CONFIG_A` is defined more than once"
+        );
+
+        // Not generated, as `CHIP_SECURE_SESSION_POOL_SIZE` is not set
+        app_config("CHIP_CONFIG_SECURE_SESSION_POOL_SIZE=8")
+            .check()
+            .unwrap();
+    }
+
+    #[test]
+    fn c_string_literals() {
+        assert_eq!(c_string_literal("/var/chip.ini"), r#""/var/chip.ini""#);
+        assert_eq!(
+            c_string_literal(r#"C:\chip "kvs"??="#),
+            r#""C:\\chip \"kvs\"\?\?=""#
+        );
+        assert_eq!(c_string_literal("a\nb\tc"), r#""a\012b\011c""#);
+
+        // UTF-8, byte by byte, and not running into the following digits
+        assert_eq!(c_string_literal("é1"), r#""\303\2511""#);
+        assert_eq!(c_string_literal("\u{1F600}"), r#""\360\237\230\200""#);
+    }
+}
@@ -0,0 +1,143 @@
+//! The cargo features of the crate, as declared in its manifest.
+//!
+//! Used by the build script, and kept free of its environment (cargo variables and SDK checkout)
+//! so that it can be unit-tested.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+
+/// The features the `default` feature of the `manifest` (a `Cargo.toml`) enables, transitively
+///
+/// Only the features of the crate itself are returned, and not the ones of its dependencies
+/// (`dep:name`, `name/feature` and `name?/feature`). An optional dependency enabled by its
+/// implicit feature is returned as that feature.
+pub fn default_features(manifest: &str) -> Result<Vec<String>> {
+    let features = parse_features(manifest)?;
+
+    let mut enabled = Vec::new();
+    let mut pending = vec!["default".to_owned()];
+
+    while let Some(feature) = pending.pop() {
+        for implied in features.get(&feature).into_iter().flatten() {
+            if implied.starts_with("dep:") || implied.contains('/') {
+                continue;
+            }
+
+            if !enabled.contains(implied) {
+                enabled.push(implied.clone());
+                pending.push(implied.clone());
+            }
+        }
+    }
+
+    enabled.sort();
+
+    Ok(enabled)
+}
+
+/// The `[features]` table of `manifest`, as `feature -> [implied features]`
+///
+/// Not a TOML parser: it expects the table to only have `name = ["value", ...]` entries,
+/// which is all cargo allows there anyway.
+fn parse_features(manifest: &str) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut table = String::new();
+    let mut in_table = false;
+    let mut in_array = false;
+
+    for line in manifest.lines() {
+        // Neither feature names nor the TOML syntax used for them contain `#`
+        let line = line.split('#').next().unwrap().trim();
+
+        if !in_array && line.starts_with('[') {
+            in_table = line == "[features]";
+            continue;
+        }
+
+        if in_table {
+            table.push_str(line);
+            table.push('\n');
+
+            in_array = table.matches('[').count() > table.matches(']').count();
+        }
+    }
+
+    let mut features = BTreeMap::new();
+    let mut rest = table.as_str();
+
+    while let Some((name, value)) = rest.split_once('=') {
+        let name = name.trim().trim_matches('"');
+
+        let value = value.trim_start();
+        let end = value
+            .strip_prefix('[')
+            .and_then(|value| value.find(']'))
+            .ok_or_else(|| anyhow!("Feature `{name}`: expected an array of features"))?;
+
+        let implied = value[1..=end]
+            .split(',')
+            .map(|implied| implied.trim().trim_matches('"'))
+            .filter(|implied| !implied.is_empty())
+            .map(str::to_owned)
+            .collect();
+
+        features.insert(name.to_owned(), implied);
+
+        rest = &value[end + 2..];
+    }
+
+    Ok(features)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features() {
+        let manifest = r#"
+[package]
+name = "crate"
+links = "lib"
+
+[features]
+# The features enabled by default
+default = ["std", "log?/std", "dep:serde", "log"]
+std = ["alloc"] # Comment
+alloc = []
+"multi-line" = [
+    "std",
+    "unused",
+]
+unused = ["dep:other"]
+
+[dependencies]
+log = { version = "0.4", optional = true, features = ["std"] }
+"#;
+
+        let features = parse_features(manifest).unwrap();
+
+        assert_eq!(features.len(), 5);
+        assert_eq!(features["multi-line"], ["std", "unused"]);
+        assert!(features["alloc"].is_empty());
+
+        assert_eq!(default_features(manifest).unwrap(), ["alloc", "log", "std"]);
+
+        assert_eq!(
+            default_features(&manifest.replace(r#""log"]"#, r#""log", "multi-line"]"#)).unwrap(),
+            ["alloc", "log", "multi-line", "std", "unused"]
+        );
+
+        assert!(default_features("[package]\nname = \"crate\"\n")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn crate_features() {
+        assert_eq!(
+            default_features(include_str!("../Cargo.toml")).unwrap(),
+            ["alloc", "log", "std"]
+        );
+    }
+}
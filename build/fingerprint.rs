@@ -0,0 +1,125 @@
+//! The fingerprint of a CHIPALL build, which a prebuilt CHIPALL is checked against.
+//!
+//! Used by the build script, and kept free of its environment (cargo variables and SDK checkout)
+//! so that it can be unit-tested.
+
+use std::fmt;
+
+/// Everything which went into a CHIPALL build, so that a prebuilt CHIPALL
+/// is only ever used with the configuration it was built for
+pub struct Fingerprint(pub Vec<(&'static str, String)>);
+
+impl Fingerprint {
+    pub fn parse(fingerprint: &str) -> Vec<(String, String)> {
+        fingerprint
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect()
+    }
+
+    /// Describe the entries of this fingerprint which are missing from, or differ in, the
+    /// `prebuilt` one.
+    ///
+    /// Entries only the `prebuilt` fingerprint has are not checked, e.g. the SDK commit, when
+    /// it cannot be determined without a checkout or network access.
+    pub fn mismatches(&self, prebuilt: &[(String, String)]) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(|(key, value)| {
+                let actual = prebuilt
+                    .iter()
+                    .find(|(pkey, _)| pkey == key)
+                    .map(|(_, pvalue)| pvalue.as_str());
+
+                (actual != Some(value.as_str())).then(|| {
+                    format!(
+                        "  {key}: expected `{value}`, prebuilt has `{}`",
+                        actual.unwrap_or("(missing)")
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, value) in &self.0 {
+            writeln!(f, "{key}={value}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(entries: &[(&'static str, &str)]) -> Fingerprint {
+        Fingerprint(
+            entries
+                .iter()
+                .map(|(key, value)| (*key, value.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let expected = fingerprint(&[
+            ("sdk-ref", "branch:v1.0-branch"),
+            ("gn-args", "is_debug=true chip_detail_logging=false"),
+            ("template-endpoints", ""),
+        ]);
+
+        let prebuilt = Fingerprint::parse(&expected.to_string());
+
+        assert_eq!(prebuilt.len(), 3);
+        assert_eq!(
+            prebuilt[1],
+            (
+                "gn-args".to_owned(),
+                "is_debug=true chip_detail_logging=false".to_owned()
+            )
+        );
+
+        assert!(expected.mismatches(&prebuilt).is_empty());
+    }
+
+    #[test]
+    fn mismatches() {
+        let prebuilt = Fingerprint::parse(
+            "sdk-ref=branch:v1.0-branch\n\
+             sdk-commit=0123456789abcdef\n\
+             features=log,std\n\
+             target=x86_64-unknown-linux-gnu\n",
+        );
+
+        // Only what is expected is checked
+        assert!(fingerprint(&[("sdk-ref", "branch:v1.0-branch")])
+            .mismatches(&prebuilt)
+            .is_empty());
+
+        assert_eq!(
+            fingerprint(&[
+                ("sdk-ref", "branch:v1.0-branch"),
+                ("sdk-commit", "fedcba9876543210"),
+                ("features", "log,std"),
+                ("patches", "0000000000000001"),
+            ])
+            .mismatches(&prebuilt),
+            [
+                "  sdk-commit: expected `fedcba9876543210`, prebuilt has `0123456789abcdef`",
+                "  patches: expected `0000000000000001`, prebuilt has `(missing)`",
+            ]
+        );
+
+        // An empty value is not the same as a missing one
+        assert_eq!(
+            fingerprint(&[("patches", "")]).mismatches(&prebuilt),
+            ["  patches: expected ``, prebuilt has `(missing)`"]
+        );
+    }
+}
@@ -0,0 +1,360 @@
+//! The files of the data model CHIPALL is built against.
+//!
+//! Used by the build script, and kept free of its environment (cargo variables and SDK checkout)
+//! so that it can be unit-tested.
+
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use serde_json::Value;
+
+use crate::idl::{ClusterSide, Idl};
+
+/// Copy the `.zap` file `zap_file` into `dir`, along with its `.matter` IDL if any, and return
+/// the location of the copy.
+///
+/// The `relativeToZap` paths of its packages (the ZCL data and the code generation templates
+/// of the SDK, e.g. `../../../src/app/zap-templates/zcl/zcl.json`) are made absolute,
+/// as resolved against the location of `zap_file`, so that the ZAP tool still finds them
+/// when run on the copy.
+pub fn copy_zap_file(zap_file: &Path, dir: &Path) -> Result<PathBuf> {
+    let zap_dir = zap_file
+        .parent()
+        .ok_or_else(|| anyhow!("{}: not a file", zap_file.display()))?;
+
+    let mut zap = read_zap_file(zap_file)?;
+
+    rebase_packages(&mut zap, zap_dir).map_err(|err| anyhow!("{}: {err}", zap_file.display()))?;
+
+    fs::create_dir_all(dir)?;
+
+    let copy = dir.join(zap_file.file_name().unwrap());
+    write_zap_file(&copy, &zap)?;
+
+    let matter_file = zap_file.with_extension("matter");
+
+    if matter_file.exists() {
+        fs::copy(&matter_file, copy.with_extension("matter"))?;
+    }
+
+    Ok(copy)
+}
+
+pub fn read_zap_file(zap_file: &Path) -> Result<Value> {
+    serde_json::from_str(&fs::read_to_string(zap_file)?)
+        .map_err(|err| anyhow!("{}: {err}", zap_file.display()))
+}
+
+pub fn write_zap_file(zap_file: &Path, zap: &Value) -> Result<()> {
+    fs::write(zap_file, serde_json::to_string_pretty(zap)?)?;
+
+    Ok(())
+}
+
+/// Disable the server clusters of all endpoint types of `zap` which are not in `server_clusters`,
+/// and return the IDs of the server clusters left enabled
+pub fn trim_server_clusters(zap: &mut Value, server_clusters: &[u32]) -> Result<Vec<u32>> {
+    let endpoint_types = zap
+        .get_mut("endpointTypes")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| anyhow!("no `endpointTypes`"))?;
+
+    let clusters = endpoint_types
+        .iter_mut()
+        .filter_map(|endpoint_type| endpoint_type.get_mut("clusters"))
+        .filter_map(Value::as_array_mut)
+        .flatten()
+        .filter(|cluster| cluster["side"] == "server" && cluster["enabled"] == 1);
+
+    let mut enabled = Vec::new();
+
+    for cluster in clusters {
+        let id = cluster["code"].as_u64().unwrap_or_default() as u32;
+
+        if !server_clusters.contains(&id) {
+            cluster["enabled"] = 0.into();
+        } else if !enabled.contains(&id) {
+            enabled.push(id);
+        }
+    }
+
+    Ok(enabled)
+}
+
+/// Drop the server clusters which are not in `server_clusters` from `idl`, both from its
+/// cluster definitions and from the clusters its endpoints instantiate
+pub fn filter_idl(idl: &mut Idl, server_clusters: &[u32]) {
+    let trimmed = idl
+        .clusters
+        .iter()
+        .filter(|cluster| {
+            cluster.side == ClusterSide::Server && !server_clusters.contains(&cluster.code)
+        })
+        .map(|cluster| cluster.name.clone())
+        .collect::<Vec<_>>();
+
+    idl.clusters
+        .retain(|cluster| cluster.side != ClusterSide::Server || !trimmed.contains(&cluster.name));
+
+    for endpoint in &mut idl.endpoints {
+        endpoint
+            .server_clusters
+            .retain(|cluster| !trimmed.contains(&cluster.name));
+    }
+}
+
+/// Make the `relativeToZap` package paths of `zap` absolute, as resolved against `zap_dir`
+fn rebase_packages(zap: &mut Value, zap_dir: &Path) -> Result<()> {
+    let packages = zap
+        .get_mut("package")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter(|package| package["pathRelativity"] == "relativeToZap");
+
+    for package in packages {
+        let path = package["path"]
+            .as_str()
+            .ok_or_else(|| anyhow!("package without a `path`"))?;
+
+        package["path"] = normalize(&zap_dir.join(path)).display().to_string().into();
+        package["pathRelativity"] = "absolute".into();
+    }
+
+    Ok(())
+}
+
+/// `/a/b/../c` -> `/a/c`, without touching the file system, as the path need not exist
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn copy_relative_zap_file() {
+        let root = tempfile::tempdir().unwrap();
+        let sdk = root.path().join("sdk");
+
+        let zcl = sdk.join("src/app/zap-templates/zcl/zcl.json");
+        fs::create_dir_all(zcl.parent().unwrap()).unwrap();
+        fs::write(&zcl, "{}").unwrap();
+
+        let zap_file = sdk.join("examples/light/light-common/light.zap");
+        fs::create_dir_all(zap_file.parent().unwrap()).unwrap();
+
+        fs::write(
+            &zap_file,
+            json!({
+                "featureLevel": 80,
+                "package": [
+                    {
+                        "pathRelativity": "relativeToZap",
+                        "path": "../../../src/app/zap-templates/zcl/zcl.json",
+                        "type": "zcl-properties",
+                    },
+                    {
+                        "pathRelativity": "relativeToZap",
+                        "path": "./../../../src/app/zap-templates/app-templates.json",
+                        "type": "gen-templates-json",
+                    },
+                    {
+                        "pathRelativity": "relativeToHome",
+                        "path": "templates/app-templates.json",
+                        "type": "gen-templates-json",
+                    },
+                ],
+                "endpointTypes": [],
+            })
+            .to_string(),
+        )
+        .unwrap();
+        fs::write(zap_file.with_extension("matter"), "endpoint 0 {}").unwrap();
+
+        let out_dir = root.path().join("out/chip/zap");
+
+        let copy = copy_zap_file(&zap_file, &out_dir).unwrap();
+        assert_eq!(copy, out_dir.join("light.zap"));
+
+        assert_eq!(
+            fs::read_to_string(copy.with_extension("matter")).unwrap(),
+            "endpoint 0 {}"
+        );
+
+        let zap = read_zap_file(&copy).unwrap();
+        let packages = zap["package"].as_array().unwrap();
+
+        assert_eq!(packages[0]["pathRelativity"], "absolute");
+        assert_eq!(packages[0]["path"], zcl.display().to_string());
+        assert!(Path::new(packages[0]["path"].as_str().unwrap()).is_file());
+
+        assert_eq!(packages[1]["pathRelativity"], "absolute");
+        assert_eq!(
+            packages[1]["path"],
+            sdk.join("src/app/zap-templates/app-templates.json")
+                .display()
+                .to_string()
+        );
+
+        assert_eq!(packages[2]["pathRelativity"], "relativeToHome");
+        assert_eq!(packages[2]["path"], "templates/app-templates.json");
+
+        assert_eq!(zap["featureLevel"], 80);
+    }
+
+    #[test]
+    fn copy_zap_file_without_idl() {
+        let root = tempfile::tempdir().unwrap();
+
+        let zap_file = root.path().join("model.zap");
+        fs::write(&zap_file, r#"{"endpointTypes": []}"#).unwrap();
+
+        let copy = copy_zap_file(&zap_file, &root.path().join("out")).unwrap();
+
+        assert!(copy.is_file());
+        assert!(!copy.with_extension("matter").exists());
+    }
+
+    #[test]
+    fn trim() {
+        let mut zap = json!({
+            "endpointTypes": [
+                {
+                    "name": "Root",
+                    "clusters": [
+                        { "name": "Descriptor", "code": 29, "side": "server", "enabled": 1 },
+                        { "name": "Identify", "code": 3, "side": "client", "enabled": 1 },
+                        { "name": "Identify", "code": 3, "side": "server", "enabled": 1 },
+                    ],
+                },
+                {
+                    "name": "Light",
+                    "clusters": [
+                        { "name": "Descriptor", "code": 29, "side": "server", "enabled": 1 },
+                        { "name": "On/Off", "code": 6, "side": "server", "enabled": 1 },
+                        { "name": "Level Control", "code": 8, "side": "server", "enabled": 0 },
+                    ],
+                },
+            ],
+        });
+
+        assert_eq!(
+            trim_server_clusters(&mut zap, &[29, 6, 8]).unwrap(),
+            [29, 6]
+        );
+
+        let enabled = |endpoint_type: usize, cluster: usize| {
+            zap["endpointTypes"][endpoint_type]["clusters"][cluster]["enabled"].clone()
+        };
+
+        assert_eq!(enabled(0, 0), 1);
+        assert_eq!(enabled(0, 1), 1); // Client clusters are left alone
+        assert_eq!(enabled(0, 2), 0);
+        assert_eq!(enabled(1, 0), 1);
+        assert_eq!(enabled(1, 1), 1);
+        assert_eq!(enabled(1, 2), 0);
+
+        assert!(trim_server_clusters(&mut json!({}), &[]).is_err());
+    }
+
+    #[test]
+    fn filter() {
+        let mut idl = Idl::parse(
+            r#"
+            client cluster Identify = 3 {
+              attribute int16u identifyTime = 0;
+            }
+
+            server cluster Identify = 3 {
+              attribute int16u identifyTime = 0;
+            }
+
+            server cluster OnOff = 6 {
+              readonly attribute boolean onOff = 0;
+            }
+
+            server cluster Descriptor = 29 {
+              readonly attribute int16u clusterRevision = 65533;
+            }
+
+            endpoint 0 {
+              device type rootdevice = 22, version 1;
+
+              server cluster Descriptor {
+                callback attribute clusterRevision;
+              }
+
+              server cluster Identify {
+                ram attribute identifyTime;
+              }
+            }
+            endpoint 1 {
+              device type onofflight = 256, version 1;
+
+              server cluster OnOff {
+                ram attribute onOff;
+              }
+
+              server cluster Identify {
+                ram attribute identifyTime;
+              }
+            }
+            "#,
+        )
+        .unwrap();
+
+        filter_idl(&mut idl, &[6, 29]);
+
+        let clusters = idl
+            .clusters
+            .iter()
+            .map(|cluster| (cluster.side, cluster.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            clusters,
+            [
+                (ClusterSide::Client, "Identify"),
+                (ClusterSide::Server, "OnOff"),
+                (ClusterSide::Server, "Descriptor"),
+            ]
+        );
+
+        let instantiated = |number: u16| {
+            idl.endpoint(number)
+                .unwrap()
+                .server_clusters
+                .iter()
+                .map(|cluster| cluster.name.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(instantiated(0), ["Descriptor"]);
+        assert_eq!(instantiated(1), ["OnOff"]);
+    }
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(
+            normalize(Path::new("/sdk/examples/app/../../src/./zcl.json")),
+            Path::new("/sdk/src/zcl.json")
+        );
+    }
+}
@@ -6,10 +6,10 @@ use chip_sys::chip::{
     TEST_COMISSIONABLE_DATA,
 };
 
+use chip_sys::clusters::{on_off, ClusterId};
 use chip_sys::{
-    chip_ClusterId, chip_EndpointId, ChipError, EmberAfError,
-    EmberAfStatus_EMBER_ZCL_STATUS_FAILURE, ZCL_BRIDGED_DEVICE_BASIC_CLUSTER_ID,
-    ZCL_ON_OFF_ATTRIBUTE_ID, ZCL_ON_OFF_CLUSTER_ID, ZCL_REACHABLE_ATTRIBUTE_ID,
+    glue_EndpointId, EmberAfError, ImStatus, ZCL_BRIDGED_DEVICE_BASIC_CLUSTER_ID,
+    ZCL_REACHABLE_ATTRIBUTE_ID,
 };
 
 static LIGHT_DEVICE_TYPES: DeviceTypes = &[
@@ -24,7 +24,7 @@ static LIGHT: EndpointType<'static, 'static> = {
 };
 
 pub struct LightState {
-    id: chip_EndpointId,
+    id: glue_EndpointId,
     on: Cell<bool>,
 }
 
@@ -32,8 +32,8 @@ impl EmberCallback for LightState {
     fn read(
         &self,
         _ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &mut [u8],
     ) -> Result<(), EmberAfError> {
@@ -45,8 +45,8 @@ impl EmberCallback for LightState {
 
             Ok(())
         } else if endpoint_id == self.id
-            && cluster_id == ZCL_ON_OFF_CLUSTER_ID
-            && attribute.id() == ZCL_ON_OFF_ATTRIBUTE_ID
+            && cluster_id == on_off::ID
+            && attribute.id() == on_off::attributes::ON_OFF
         {
             println!("Getting light state: {}", self.on.get());
 
@@ -54,21 +54,21 @@ impl EmberCallback for LightState {
 
             Ok(())
         } else {
-            Err(EmberAfError::from(EmberAfStatus_EMBER_ZCL_STATUS_FAILURE))
+            Err(ImStatus::UnsupportedAttribute.into())
         }
     }
 
     fn write(
         &self,
         _ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &[u8],
     ) -> Result<(), EmberAfError> {
         if endpoint_id == self.id
-            && cluster_id == ZCL_ON_OFF_CLUSTER_ID
-            && attribute.id() == ZCL_ON_OFF_ATTRIBUTE_ID
+            && cluster_id == on_off::ID
+            && attribute.id() == on_off::attributes::ON_OFF
         {
             let on = buffer[0] != 0;
 
@@ -78,12 +78,12 @@ impl EmberCallback for LightState {
 
             Ok(())
         } else {
-            Err(EmberAfError::from(EmberAfStatus_EMBER_ZCL_STATUS_FAILURE))
+            Err(ImStatus::UnsupportedWrite.into())
         }
     }
 }
 
-pub fn main() -> Result<(), ChipError> {
+pub fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Starting");
 
     let ctx = ChipContext::take()?;
@@ -102,7 +102,7 @@ pub fn main() -> Result<(), ChipError> {
         },
     )?;
 
-    BRIDGE_NODE.enable(true);
+    BRIDGE_NODE.enable(true)?;
 
     let mut data_versions = [0; 8];
 
@@ -112,8 +112,7 @@ pub fn main() -> Result<(), ChipError> {
         &LIGHT,
         &mut data_versions,
         BRIDGE_NODE,
-    )
-    .unwrap();
+    )?;
 
     chip.run();
 
@@ -1,66 +1,132 @@
+#include <vector>
+
 #include <app/server/Server.h>
+#include <app/server/OnboardingCodesUtil.h>
 #include <app/util/af.h>
 #include <app/util/attribute-storage.h>
 #include <app/InteractionModelEngine.h>
+#include <app/reporting/reporting.h>
 #include <app/clusters/mode-select-server/supported-modes-manager.h>
+#include <credentials/DeviceAttestationCredsProvider.h>
+#include <credentials/examples/DeviceAttestationCredsExample.h>
 #include <lib/core/CHIPError.h>
+#include <platform/CHIPDeviceLayer.h>
 #include <platform/CommissionableDataProvider.h>
 #include "glue.h"
 
 using namespace ::chip;
 
+static_assert(sizeof(glue_EndpointId) == sizeof(EndpointId), "EndpointId");
+static_assert(sizeof(glue_ClusterId) == sizeof(ClusterId), "ClusterId");
+static_assert(sizeof(glue_AttributeId) == sizeof(AttributeId), "AttributeId");
+static_assert(sizeof(glue_CommandId) == sizeof(CommandId), "CommandId");
+static_assert(sizeof(glue_DataVersion) == sizeof(DataVersion), "DataVersion");
+static_assert(GLUE_INVALID_ENDPOINT_ID == kInvalidEndpointId, "kInvalidEndpointId");
+static_assert(GLUE_INVALID_COMMAND_ID == kInvalidCommandId, "kInvalidCommandId");
+static_assert(GLUE_RENDEZVOUS_SOFT_AP == static_cast<uint8_t>(RendezvousInformationFlag::kSoftAP), "kSoftAP");
+static_assert(GLUE_RENDEZVOUS_BLE == static_cast<uint8_t>(RendezvousInformationFlag::kBLE), "kBLE");
+static_assert(GLUE_RENDEZVOUS_ON_NETWORK == static_cast<uint8_t>(RendezvousInformationFlag::kOnNetwork), "kOnNetwork");
+
 extern "C" bool gluecb_emberAfActionsClusterInstantActionCallback(
-    app::CommandHandler* commandObj, 
-    const app::ConcreteCommandPath* commandPath,
-    const app::Clusters::Actions::Commands::InstantAction::DecodableType* commandData
+    glue_CommandHandler* commandObj,
+    const glue_CommandPath* commandPath,
+    const glue_InstantAction* commandData
 );
 
-extern "C" EmberAfStatus gluecb_emberAfExternalAttributeReadCallback(
-    EndpointId endpoint, 
-    ClusterId clusterId,
-    const EmberAfAttributeMetadata* attributeMetadata, 
+extern "C" glue_EmberAfStatus gluecb_emberAfExternalAttributeReadCallback(
+    glue_EndpointId endpoint,
+    glue_ClusterId clusterId,
+    const glue_AttributeMetadata* attributeMetadata,
     uint8_t* buffer,
     uint16_t maxReadLength
 );
 
-extern "C" EmberAfStatus gluecb_emberAfExternalAttributeWriteCallback(
-    EndpointId endpoint, 
-    ClusterId clusterId,
-    const EmberAfAttributeMetadata* attributeMetadata, 
+extern "C" glue_EmberAfStatus gluecb_emberAfExternalAttributeWriteCallback(
+    glue_EndpointId endpoint,
+    glue_ClusterId clusterId,
+    const glue_AttributeMetadata* attributeMetadata,
     uint8_t* buffer
 );
 
-extern "C" bool gluecb_MatterActionsPluginServerInitCallback();
+extern "C" void gluecb_MatterActionsPluginServerInitCallback();
+
+// The byte buffers are passed with their capacity in `*len`, which is set to the length of the data on return
+extern "C" glue_ChipError gluecb_CommissionableDataProvider_GetSetupDiscriminator(uint16_t* setupDiscriminator);
+extern "C" glue_ChipError gluecb_CommissionableDataProvider_GetSpake2pIterationCount(uint32_t* iterationCount);
+extern "C" glue_ChipError gluecb_CommissionableDataProvider_GetSpake2pSalt(uint8_t* saltBuf, size_t* len);
+extern "C" glue_ChipError gluecb_CommissionableDataProvider_GetSpake2pVerifier(uint8_t* verifierBuf, size_t* len);
+extern "C" glue_ChipError gluecb_CommissionableDataProvider_GetSetupPasscode(uint32_t* setupPasscode);
+
+namespace glue {
+    glue_ChipError ToGlue(CHIP_ERROR error) {
+        glue_ChipError result = { error.AsInteger(), nullptr, 0 };
+
+#if CHIP_CONFIG_ERROR_SOURCE
+        result.file = error.GetFile();
+        result.line = error.GetLine();
+#endif
+
+        return result;
+    }
+
+    CHIP_ERROR FromGlue(glue_ChipError error) {
+#if CHIP_CONFIG_ERROR_SOURCE
+        if (error.file != nullptr) {
+            return CHIP_ERROR(error.code, error.file, error.line);
+        }
+#endif
+
+        return CHIP_ERROR(error.code);
+    }
+
+    glue_AttributeMetadata ToGlue(const EmberAfAttributeMetadata* attributeMetadata) {
+        glue_AttributeMetadata result = {
+            attributeMetadata->attributeId,
+            attributeMetadata->attributeType,
+            attributeMetadata->size,
+            attributeMetadata->mask,
+        };
 
-extern "C" CHIP_ERROR gluecb_CommissionableDataProvider_GetSetupDiscriminator(uint16_t* setupDiscriminator);
-extern "C" CHIP_ERROR gluecb_CommissionableDataProvider_GetSpake2pIterationCount(uint32_t* iterationCount);
-extern "C" CHIP_ERROR gluecb_CommissionableDataProvider_GetSpake2pSalt(MutableByteSpan* saltBuf);
-extern "C" CHIP_ERROR gluecb_CommissionableDataProvider_GetSpake2pVerifier(MutableByteSpan* verifierBuf, size_t* outVerifierLen);
-extern "C" CHIP_ERROR gluecb_CommissionableDataProvider_GetSetupPasscode(uint32_t* setupPasscode);
+        return result;
+    }
+}
 
 bool emberAfActionsClusterInstantActionCallback(
-    app::CommandHandler* commandObj, 
+    app::CommandHandler* commandObj,
     const app::ConcreteCommandPath& commandPath,
     const app::Clusters::Actions::Commands::InstantAction::DecodableType& commandData) {
-    
-    return gluecb_emberAfActionsClusterInstantActionCallback(commandObj, &commandPath, &commandData);
+    glue_CommandPath path = { commandPath.mEndpointId, commandPath.mClusterId, commandPath.mCommandId };
+    glue_InstantAction data = {
+        commandData.actionID,
+        commandData.invokeID.HasValue(),
+        commandData.invokeID.ValueOr(0),
+    };
+
+    return gluecb_emberAfActionsClusterInstantActionCallback(
+        reinterpret_cast<glue_CommandHandler*>(commandObj), &path, &data);
 }
 
 EmberAfStatus emberAfExternalAttributeReadCallback(
-    EndpointId endpoint, 
+    EndpointId endpoint,
     ClusterId clusterId,
-    const EmberAfAttributeMetadata* attributeMetadata, 
+    const EmberAfAttributeMetadata* attributeMetadata,
     uint8_t* buffer,
     uint16_t maxReadLength) {
-    return gluecb_emberAfExternalAttributeReadCallback(endpoint, clusterId, attributeMetadata, buffer, maxReadLength);
+    glue_AttributeMetadata metadata = glue::ToGlue(attributeMetadata);
+
+    return static_cast<EmberAfStatus>(
+        gluecb_emberAfExternalAttributeReadCallback(endpoint, clusterId, &metadata, buffer, maxReadLength));
 }
 
 EmberAfStatus emberAfExternalAttributeWriteCallback(
-    EndpointId endpoint, 
+    EndpointId endpoint,
     ClusterId clusterId,
-    const EmberAfAttributeMetadata* attributeMetadata, 
+    const EmberAfAttributeMetadata* attributeMetadata,
     uint8_t* buffer) {
-    return gluecb_emberAfExternalAttributeWriteCallback(endpoint, clusterId, attributeMetadata, buffer);
+    glue_AttributeMetadata metadata = glue::ToGlue(attributeMetadata);
+
+    return static_cast<EmberAfStatus>(
+        gluecb_emberAfExternalAttributeWriteCallback(endpoint, clusterId, &metadata, buffer));
 }
 
 void MatterActionsPluginServerInitCallback() {
@@ -88,7 +154,7 @@ namespace glue {
         virtual ~CommissionableDataProvider() {}
 
         CHIP_ERROR GetSetupDiscriminator(uint16_t& setupDiscriminator) override {
-            return gluecb_CommissionableDataProvider_GetSetupDiscriminator(&setupDiscriminator);
+            return FromGlue(gluecb_CommissionableDataProvider_GetSetupDiscriminator(&setupDiscriminator));
         }
 
         CHIP_ERROR SetSetupDiscriminator(uint16_t setupDiscriminator) override {
@@ -96,19 +162,36 @@ namespace glue {
         }
 
         CHIP_ERROR GetSpake2pIterationCount(uint32_t& iterationCount) override {
-            return gluecb_CommissionableDataProvider_GetSpake2pIterationCount(&iterationCount);
+            return FromGlue(gluecb_CommissionableDataProvider_GetSpake2pIterationCount(&iterationCount));
         }
 
         CHIP_ERROR GetSpake2pSalt(MutableByteSpan& saltBuf) override {
-            return gluecb_CommissionableDataProvider_GetSpake2pSalt(&saltBuf);
+            size_t len = saltBuf.size();
+
+            ReturnErrorOnFailure(FromGlue(gluecb_CommissionableDataProvider_GetSpake2pSalt(saltBuf.data(), &len)));
+
+            saltBuf.reduce_size(len);
+
+            return CHIP_NO_ERROR;
         }
 
         CHIP_ERROR GetSpake2pVerifier(MutableByteSpan& verifierBuf, size_t& outVerifierLen) override {
-            return gluecb_CommissionableDataProvider_GetSpake2pVerifier(&verifierBuf, &outVerifierLen);
+            size_t len = verifierBuf.size();
+
+            CHIP_ERROR error = FromGlue(gluecb_CommissionableDataProvider_GetSpake2pVerifier(verifierBuf.data(), &len));
+
+            // Also reported on `CHIP_ERROR_BUFFER_TOO_SMALL`, as per the `CommissionableDataProvider` contract
+            outVerifierLen = len;
+
+            ReturnErrorOnFailure(error);
+
+            verifierBuf.reduce_size(len);
+
+            return CHIP_NO_ERROR;
         }
 
         CHIP_ERROR GetSetupPasscode(uint32_t& setupPasscode) override {
-            return gluecb_CommissionableDataProvider_GetSetupPasscode(&setupPasscode);
+            return FromGlue(gluecb_CommissionableDataProvider_GetSetupPasscode(&setupPasscode));
         }
 
         CHIP_ERROR SetSetupPasscode(uint32_t setupPasscode) override {
@@ -116,14 +199,236 @@ namespace glue {
         }
     };
 
+    // The SDK copies of the endpoint types and device types passed from Rust, as it keeps pointers to them
+    struct EndpointStorage {
+        EmberAfEndpointType endpointType;
+        std::vector<EmberAfCluster> clusters;
+        std::vector<EmberAfAttributeMetadata> attributes;
+        std::vector<EmberAfDeviceType> deviceTypes;
+
+        void SetDeviceTypes(const glue_DeviceType* types, size_t count) {
+            deviceTypes.clear();
+
+            for (size_t index = 0; index < count; index++) {
+                EmberAfDeviceType deviceType = { types[index].deviceId, types[index].deviceVersion };
+                deviceTypes.push_back(deviceType);
+            }
+        }
+
+        const EmberAfEndpointType* SetEndpointType(const glue_EndpointType* type) {
+            clusters.clear();
+            attributes.clear();
+
+            size_t attributeCount = 0;
+
+            for (uint8_t index = 0; index < type->clusterCount; index++) {
+                attributeCount += type->cluster[index].attributeCount;
+            }
+
+            // Reserved upfront, so that the clusters can point into it
+            attributes.reserve(attributeCount);
+
+            for (uint8_t index = 0; index < type->clusterCount; index++) {
+                const glue_Cluster& glueCluster = type->cluster[index];
+
+                EmberAfCluster cluster = {};
+                cluster.clusterId = glueCluster.clusterId;
+                cluster.attributes = attributes.data() + attributes.size();
+                cluster.attributeCount = glueCluster.attributeCount;
+                cluster.clusterSize = 0;
+                cluster.mask = CLUSTER_MASK_SERVER;
+                cluster.functions = nullptr;
+                cluster.acceptedCommandList = glueCluster.acceptedCommandList;
+                cluster.generatedCommandList = glueCluster.generatedCommandList;
+
+                for (uint16_t attrIndex = 0; attrIndex < glueCluster.attributeCount; attrIndex++) {
+                    const glue_AttributeMetadata& glueAttribute = glueCluster.attributes[attrIndex];
+
+                    EmberAfAttributeMetadata attribute = {
+                        { static_cast<uint32_t>(0) },
+                        glueAttribute.attributeId,
+                        glueAttribute.size,
+                        glueAttribute.attributeType,
+                        static_cast<EmberAfAttributeMask>(glueAttribute.mask | ATTRIBUTE_MASK_EXTERNAL_STORAGE),
+                    };
+                    attributes.push_back(attribute);
+                }
+
+                clusters.push_back(cluster);
+            }
+
+            endpointType.cluster = clusters.data();
+            endpointType.clusterCount = type->clusterCount;
+            endpointType.endpointSize = 0;
+
+            return &endpointType;
+        }
+
+        void Clear() {
+            clusters = std::vector<EmberAfCluster>();
+            attributes = std::vector<EmberAfAttributeMetadata>();
+            deviceTypes = std::vector<EmberAfDeviceType>();
+        }
+    };
+
     CommissionableDataProvider glueg_CommissionableDataProvider;
-    chip::CommonCaseDeviceServerInitParams glueg_CommonCaseDeviceServerInitParams;
+    CommonCaseDeviceServerInitParams glueg_CommonCaseDeviceServerInitParams;
+    EndpointStorage glueg_Endpoints[FIXED_ENDPOINT_COUNT + CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT];
+}
+
+extern "C" {
+    void glue_Initialize() {
+        DeviceLayer::SetCommissionableDataProvider(&glue::glueg_CommissionableDataProvider);
+    }
+
+    glue_ChipError glue_MemoryInit() {
+        return glue::ToGlue(Platform::MemoryInit());
+    }
+
+    glue_ChipError glue_InitChipStack() {
+        return glue::ToGlue(DeviceLayer::PlatformMgr().InitChipStack());
+    }
+
+    void glue_RunEventLoop() {
+        DeviceLayer::PlatformMgr().RunEventLoop();
+    }
+
+    glue_ChipError glue_StartEventLoopTask() {
+        return glue::ToGlue(DeviceLayer::PlatformMgr().StartEventLoopTask());
+    }
+
+    glue_ChipError glue_StopEventLoopTask() {
+        return glue::ToGlue(DeviceLayer::PlatformMgr().StopEventLoopTask());
+    }
+
+    void glue_LockChipStack() {
+        DeviceLayer::PlatformMgr().LockChipStack();
+    }
+
+    void glue_UnlockChipStack() {
+        DeviceLayer::PlatformMgr().UnlockChipStack();
+    }
+
+    void glue_ScheduleWork(glue_AsyncWork work, intptr_t context) {
+        DeviceLayer::PlatformMgr().ScheduleWork(work, context);
+    }
+
+    void glue_SetExampleDACProvider() {
+        Credentials::SetDeviceAttestationCredentialsProvider(Credentials::Examples::GetExampleDACProvider());
+    }
+
+    glue_ChipError glue_StoreVendorId(uint16_t vendorId) {
+        return glue::ToGlue(DeviceLayer::ConfigurationManagerImpl::GetDefaultInstance().StoreVendorId(vendorId));
+    }
+
+    glue_ChipError glue_StoreProductId(uint16_t productId) {
+        return glue::ToGlue(DeviceLayer::ConfigurationManagerImpl::GetDefaultInstance().StoreProductId(productId));
+    }
+
+    glue_ChipError glue_InitServer() {
+        CommonCaseDeviceServerInitParams& initParams = glue::glueg_CommonCaseDeviceServerInitParams;
+
+        CHIP_ERROR error = initParams.InitializeStaticResourcesBeforeServerInit();
+        if (error != CHIP_NO_ERROR) {
+            return glue::ToGlue(error);
+        }
+
+        return glue::ToGlue(Server::GetInstance().Init(initParams));
+    }
+
+    void glue_ShutdownServer() {
+        Server::GetInstance().Shutdown();
+    }
+
+    void glue_ShutdownChipStack() {
+        DeviceLayer::PlatformMgr().Shutdown();
+    }
+
+    void glue_MemoryShutdown() {
+        Platform::MemoryShutdown();
+    }
 
-    void Initialize() {
-        SetCommissionableDataProvider(&glueg_CommissionableDataProvider);
+    void glue_PrintOnboardingCodes(uint8_t rendezvousFlags) {
+        RendezvousInformationFlags flags;
+        flags.SetRaw(rendezvousFlags);
+
+        PrintOnboardingCodes(flags);
+    }
+
+    const char* glue_ErrorAsString(glue_ChipError error) {
+        // Keeps the source location, which `ErrorStr` prepends when built with `CHIP_CONFIG_ERROR_SOURCE`
+        return ErrorStr(glue::FromGlue(error));
+    }
+
+    void glue_ReportAttributeChanged(glue_EndpointId endpoint, glue_ClusterId clusterId, glue_AttributeId attributeId) {
+        MatterReportingAttributeChangeCallback(endpoint, clusterId, attributeId);
+    }
+
+    void glue_ReportEndpointChanged(glue_EndpointId endpoint) {
+        MatterReportingAttributeChangeCallback(endpoint);
+    }
+
+    glue_EndpointId glue_EndpointFromIndex(uint16_t index) {
+        return emberAfEndpointFromIndex(index);
+    }
+
+    bool glue_EndpointEnableDisable(glue_EndpointId endpoint, bool enable) {
+        return emberAfEndpointEnableDisable(endpoint, enable);
+    }
+
+    glue_ChipError glue_SetDeviceTypeList(glue_EndpointId endpoint, const glue_DeviceType* deviceTypes, size_t deviceTypeCount) {
+        uint16_t index = emberAfIndexFromEndpoint(endpoint);
+        if (index >= FIXED_ENDPOINT_COUNT + CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT) {
+            return glue::ToGlue(CHIP_ERROR_NOT_FOUND);
+        }
+
+        glue::EndpointStorage& storage = glue::glueg_Endpoints[index];
+        storage.SetDeviceTypes(deviceTypes, deviceTypeCount);
+
+        return glue::ToGlue(emberAfSetDeviceTypeList(
+            endpoint, Span<const EmberAfDeviceType>(storage.deviceTypes.data(), storage.deviceTypes.size())));
+    }
+
+    glue_EmberAfStatus glue_SetDynamicEndpoint(
+        uint16_t index,
+        glue_EndpointId id,
+        const glue_EndpointType* endpointType,
+        glue_DataVersion* dataVersions,
+        size_t dataVersionCount,
+        const glue_DeviceType* deviceTypes,
+        size_t deviceTypeCount,
+        glue_EndpointId parentEndpointId) {
+        if (index >= CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT) {
+            return GLUE_EMBER_ZCL_STATUS_INVALID_VALUE;
+        }
+
+        glue::EndpointStorage& storage = glue::glueg_Endpoints[FIXED_ENDPOINT_COUNT + index];
+        storage.SetDeviceTypes(deviceTypes, deviceTypeCount);
+
+        EmberAfStatus status = emberAfSetDynamicEndpoint(
+            index,
+            id,
+            storage.SetEndpointType(endpointType),
+            Span<DataVersion>(dataVersions, dataVersionCount),
+            Span<const EmberAfDeviceType>(storage.deviceTypes.data(), storage.deviceTypes.size()),
+            parentEndpointId);
+
+        if (status != EMBER_ZCL_STATUS_SUCCESS) {
+            storage.Clear();
+        }
+
+        return static_cast<glue_EmberAfStatus>(status);
     }
 
-    chip::CommonCaseDeviceServerInitParams* CommonCaseDeviceServerInitParams() {
-        return &glueg_CommonCaseDeviceServerInitParams;
+    glue_EndpointId glue_ClearDynamicEndpoint(uint16_t index) {
+        if (index >= CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT) {
+            return kInvalidEndpointId;
+        }
+
+        EndpointId endpoint = emberAfClearDynamicEndpoint(index);
+
+        glue::glueg_Endpoints[FIXED_ENDPOINT_COUNT + index].Clear();
+
+        return endpoint;
     }
 }
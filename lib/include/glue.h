@@ -1,9 +1,160 @@
-#pragma once 
+#pragma once
 
-#include <app/server/Server.h>
+// The API of CHIPALL as used from Rust.
+//
+// Plain C: only plain types, macros and functions, so that the bindings generated for this header
+// neither depend on the layout of the SDK classes, nor change from one SDK version to another.
+// `glue.cpp` implements it on top of the SDK. The build configuration is in the `glue_config.h`
+// generated by the build script from `glue_config.h.in`.
 
-namespace glue {
-    void Initialize();
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
 
-    chip::CommonCaseDeviceServerInitParams* CommonCaseDeviceServerInitParams();
+typedef uint16_t glue_EndpointId;
+typedef uint32_t glue_ClusterId;
+typedef uint32_t glue_AttributeId;
+typedef uint32_t glue_CommandId;
+typedef uint32_t glue_DataVersion;
+
+#define GLUE_INVALID_ENDPOINT_ID 0xFFFF
+#define GLUE_INVALID_COMMAND_ID 0xFFFFFFFF
+
+// The `RendezvousInformationFlag` values
+#define GLUE_RENDEZVOUS_SOFT_AP 0x01
+#define GLUE_RENDEZVOUS_BLE 0x02
+#define GLUE_RENDEZVOUS_ON_NETWORK 0x04
+
+// A `CHIP_ERROR`; `file` and `line` are only set when the SDK is built with `CHIP_CONFIG_ERROR_SOURCE`
+typedef struct glue_ChipError {
+    uint32_t code;
+    const char* file;
+    uint32_t line;
+} glue_ChipError;
+
+// Same values as `EmberAfStatus`
+typedef uint8_t glue_EmberAfStatus;
+
+#define GLUE_EMBER_ZCL_STATUS_SUCCESS 0x00
+#define GLUE_EMBER_ZCL_STATUS_FAILURE 0x01
+#define GLUE_EMBER_ZCL_STATUS_NOT_AUTHORIZED 0x7E
+#define GLUE_EMBER_ZCL_STATUS_UNSUPPORTED_ENDPOINT 0x7F
+#define GLUE_EMBER_ZCL_STATUS_MALFORMED_COMMAND 0x80
+#define GLUE_EMBER_ZCL_STATUS_UNSUP_COMMAND 0x81
+#define GLUE_EMBER_ZCL_STATUS_INVALID_FIELD 0x85
+#define GLUE_EMBER_ZCL_STATUS_UNSUPPORTED_ATTRIBUTE 0x86
+#define GLUE_EMBER_ZCL_STATUS_INVALID_VALUE 0x87
+#define GLUE_EMBER_ZCL_STATUS_READ_ONLY 0x88
+#define GLUE_EMBER_ZCL_STATUS_RESOURCE_EXHAUSTED 0x89
+#define GLUE_EMBER_ZCL_STATUS_DUPLICATE_EXISTS 0x8A
+#define GLUE_EMBER_ZCL_STATUS_NOT_FOUND 0x8B
+#define GLUE_EMBER_ZCL_STATUS_UNREPORTABLE_ATTRIBUTE 0x8C
+#define GLUE_EMBER_ZCL_STATUS_INVALID_DATA_TYPE 0x8D
+#define GLUE_EMBER_ZCL_STATUS_WRITE_ONLY 0x8F
+#define GLUE_EMBER_ZCL_STATUS_DATA_VERSION_MISMATCH 0x92
+#define GLUE_EMBER_ZCL_STATUS_TIMEOUT 0x94
+#define GLUE_EMBER_ZCL_STATUS_BUSY 0x9C
+#define GLUE_EMBER_ZCL_STATUS_UNSUPPORTED_CLUSTER 0xC3
+#define GLUE_EMBER_ZCL_STATUS_NEEDS_TIMED_INTERACTION 0xC6
+#define GLUE_EMBER_ZCL_STATUS_UNSUPPORTED_EVENT 0xC7
+
+// The subset of `EmberAfAttributeMetadata` describing an externally stored attribute
+typedef struct glue_AttributeMetadata {
+    glue_AttributeId attributeId;
+    uint8_t attributeType;
+    uint16_t size;
+    uint8_t mask;
+} glue_AttributeMetadata;
+
+// The subset of `EmberAfCluster` describing a server cluster;
+// the command lists are terminated with `GLUE_INVALID_COMMAND_ID`
+typedef struct glue_Cluster {
+    glue_ClusterId clusterId;
+    const glue_AttributeMetadata* attributes;
+    uint16_t attributeCount;
+    const glue_CommandId* acceptedCommandList;
+    const glue_CommandId* generatedCommandList;
+} glue_Cluster;
+
+typedef struct glue_EndpointType {
+    const glue_Cluster* cluster;
+    uint8_t clusterCount;
+} glue_EndpointType;
+
+typedef struct glue_DeviceType {
+    uint16_t deviceId;
+    uint8_t deviceVersion;
+} glue_DeviceType;
+
+// `ConcreteCommandPath`
+typedef struct glue_CommandPath {
+    glue_EndpointId endpointId;
+    glue_ClusterId clusterId;
+    glue_CommandId commandId;
+} glue_CommandPath;
+
+// `Actions::Commands::InstantAction::DecodableType`
+typedef struct glue_InstantAction {
+    uint16_t actionId;
+    bool hasInvokeId;
+    uint32_t invokeId;
+} glue_InstantAction;
+
+// `CommandHandler`, only passed back and forth
+typedef struct glue_CommandHandler glue_CommandHandler;
+
+typedef void (*glue_AsyncWork)(intptr_t context);
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+    void glue_Initialize();
+
+    glue_ChipError glue_MemoryInit();
+    glue_ChipError glue_InitChipStack();
+    void glue_RunEventLoop();
+    glue_ChipError glue_StartEventLoopTask();
+    glue_ChipError glue_StopEventLoopTask();
+    void glue_LockChipStack();
+    void glue_UnlockChipStack();
+    void glue_ScheduleWork(glue_AsyncWork work, intptr_t context);
+
+    void glue_SetExampleDACProvider();
+    glue_ChipError glue_StoreVendorId(uint16_t vendorId);
+    glue_ChipError glue_StoreProductId(uint16_t productId);
+
+    glue_ChipError glue_InitServer();
+
+    // To be called in this order, once the event loop is stopped
+    void glue_ShutdownServer();
+    void glue_ShutdownChipStack();
+    void glue_MemoryShutdown();
+    void glue_PrintOnboardingCodes(uint8_t rendezvousFlags);
+
+    const char* glue_ErrorAsString(glue_ChipError error);
+
+    // Non-overloaded wrappers of `MatterReportingAttributeChangeCallback`
+    void glue_ReportAttributeChanged(glue_EndpointId endpoint, glue_ClusterId clusterId, glue_AttributeId attributeId);
+    void glue_ReportEndpointChanged(glue_EndpointId endpoint);
+
+    glue_EndpointId glue_EndpointFromIndex(uint16_t index);
+    bool glue_EndpointEnableDisable(glue_EndpointId endpoint, bool enable);
+
+    // The device types and the endpoint type are copied, so they need not outlive the call.
+    // The data versions' storage is used by the SDK until the endpoint is cleared.
+    glue_ChipError glue_SetDeviceTypeList(glue_EndpointId endpoint, const glue_DeviceType* deviceTypes, size_t deviceTypeCount);
+    glue_EmberAfStatus glue_SetDynamicEndpoint(
+        uint16_t index,
+        glue_EndpointId id,
+        const glue_EndpointType* endpointType,
+        glue_DataVersion* dataVersions,
+        size_t dataVersionCount,
+        const glue_DeviceType* deviceTypes,
+        size_t deviceTypeCount,
+        glue_EndpointId parentEndpointId);
+    glue_EndpointId glue_ClearDynamicEndpoint(uint16_t index);
+
+#ifdef __cplusplus
 }
+#endif
@@ -1,9 +1,14 @@
 use crate::*;
 
+/// The functions locking and unlocking the stack.
+///
+/// They need not be re-entrant, as [`LockGuard`] only locks in the outermost guard of a thread.
+pub type Lock = (&'static dyn Fn(), &'static dyn Fn());
+
 /// # Safety
 ///
 /// Set at the beginning of the program when only the main thread is alive.
-pub static mut LOCK: Option<(&'static dyn Fn(), &'static dyn Fn())> = None;
+pub static mut LOCK: Option<Lock> = None;
 
 /// # Safety
 ///
@@ -21,70 +26,237 @@ pub static mut ACTIONS_PLUGIN_SERVER_INIT: Option<&'static dyn Fn()> = None;
 pub static mut COMISSIONABLE_DATA_PROVIDER: Option<&'static dyn ComissionableDataProviderCallback> =
     None;
 
+/// Set with [`initialize`], or by [`Chip::new`](crate::chip::Chip::new) from its configuration,
+/// and read by the callbacks, which run on the thread of the event loop.
+#[cfg(feature = "std")]
+static PANIC_POLICY: std::sync::Mutex<PanicPolicy> = std::sync::Mutex::new(PanicPolicy::Report);
+
+#[cfg(feature = "std")]
+fn panic_policy() -> std::sync::MutexGuard<'static, PanicPolicy> {
+    PANIC_POLICY
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+#[cfg(feature = "std")]
+pub(crate) fn set_panic_policy(policy: PanicPolicy) {
+    *panic_policy() = policy;
+}
+
+/// What to do when a callback panics, as unwinding into the Matter stack is undefined behavior.
+///
+/// Without the `std` feature panics cannot be caught, and a panicking callback aborts.
+#[cfg(feature = "std")]
+#[derive(Copy, Clone)]
+pub enum PanicPolicy {
+    /// Log the panic and abort
+    Abort,
+    /// Log the panic and report a failure to the Matter stack: a `FAILURE` status,
+    /// `CHIP_ERROR_INTERNAL`, or an unhandled command
+    Report,
+    /// Call the hook with the name of the callback and the panic payload,
+    /// then report a failure to the Matter stack as with [`PanicPolicy::Report`].
+    ///
+    /// The hook runs on the thread of the event loop. If it panics itself, the process is aborted.
+    Hook(&'static (dyn Fn(&'static str, &(dyn core::any::Any + Send)) + Sync)),
+}
+
 pub trait EmberCallback {
     fn cluster_instant_action(
         &self,
-        command_obj: *mut chip_app_CommandHandler,
-        command_path: *const chip_app_ConcreteCommandPath,
-        command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+        command_obj: *mut glue_CommandHandler,
+        command_path: *const glue_CommandPath,
+        command_data: *const glue_InstantAction,
     ) -> bool;
 
     fn external_attribute_read(
         &self,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
-        attribute: *const EmberAfAttributeMetadata,
+        endpoint_id: glue_EndpointId,
+        cluster_id: glue_ClusterId,
+        attribute: *const glue_AttributeMetadata,
         buffer: *mut u8,
         max_read_length: u16,
-    ) -> EmberAfStatus;
+    ) -> glue_EmberAfStatus;
 
     fn external_attribute_write(
         &self,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
-        attribute_meta_data: *const EmberAfAttributeMetadata,
+        endpoint_id: glue_EndpointId,
+        cluster_id: glue_ClusterId,
+        attribute_meta_data: *const glue_AttributeMetadata,
         buffer: *const u8,
-    ) -> EmberAfStatus;
+    ) -> glue_EmberAfStatus;
 }
 
 pub trait ComissionableDataProviderCallback {
-    fn get_setup_discriminator(&self, setup_discriminator: *mut u16) -> CHIP_ERROR;
+    fn get_setup_discriminator(&self, setup_discriminator: *mut u16) -> glue_ChipError;
 
-    fn get_setup_passcode(&self, setup_passcode: *mut u32) -> CHIP_ERROR;
+    fn get_setup_passcode(&self, setup_passcode: *mut u32) -> glue_ChipError;
 
-    fn get_spake2p_iteration_count(&self, iteration_count: *mut u32) -> CHIP_ERROR;
+    fn get_spake2p_iteration_count(&self, iteration_count: *mut u32) -> glue_ChipError;
 
-    fn get_spake2p_salt(&self, salt_buf: *mut chip_MutableByteSpan) -> CHIP_ERROR;
+    /// `len` is the capacity of `salt_buf` on entry, and the length of the salt on return
+    fn get_spake2p_salt(&self, salt_buf: *mut u8, len: *mut usize) -> glue_ChipError;
 
-    fn get_spake2p_verifier(
-        &self,
-        verifier_buf: *mut chip_MutableByteSpan,
-        out_verifier_len: *mut usize,
-    ) -> CHIP_ERROR;
+    /// `len` is the capacity of `verifier_buf` on entry, and the length of the verifier on return
+    fn get_spake2p_verifier(&self, verifier_buf: *mut u8, len: *mut usize) -> glue_ChipError;
+}
+
+#[cfg(feature = "std")]
+std::thread_local! {
+    /// How many [`LockGuard`]s the current thread holds
+    static LOCK_DEPTH: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// Enter a [`LockGuard`], returning `true` if it is the outermost one of the current thread.
+#[cfg(feature = "std")]
+fn enter() -> bool {
+    LOCK_DEPTH.with(|depth| {
+        depth.set(depth.get() + 1);
+        depth.get() == 1
+    })
+}
+
+/// Leave a [`LockGuard`], returning `true` if it was the outermost one of the current thread.
+#[cfg(feature = "std")]
+fn leave() -> bool {
+    LOCK_DEPTH.with(|depth| {
+        depth.set(depth.get() - 1);
+        depth.get() == 0
+    })
+}
+
+#[cfg(not(feature = "std"))]
+fn enter() -> bool {
+    true
+}
+
+#[cfg(not(feature = "std"))]
+fn leave() -> bool {
+    true
+}
+
+/// Whether the current thread holds the lock, or runs a callback of the stack, which holds it.
+#[cfg(feature = "std")]
+pub(crate) fn is_locked_by_current_thread() -> bool {
+    LOCK_DEPTH.with(|depth| depth.get() > 0)
+}
+
+/// Holds the lock set in [`LOCK`], if any, until dropped.
+///
+/// Re-entrant with the `std` feature, as the lock depth of each thread is tracked, and only the
+/// outermost guard of a thread locks and unlocks. Without it, guards must not be nested, and
+/// callbacks - which run with the lock held by the stack - must not create any.
+pub struct LockGuard(Option<&'static dyn Fn()>);
+
+impl LockGuard {
+    pub fn new() -> Self {
+        let unlock = unsafe { LOCK }.map(|(lock, unlock)| {
+            if enter() {
+                lock();
+            }
+
+            unlock
+        });
+
+        Self(unlock)
+    }
+
+    /// A guard for code the stack calls with the lock held, so that it does not lock again.
+    pub(crate) fn held() -> Self {
+        if unsafe { LOCK }.is_some() {
+            enter();
+
+            Self(Some(&|| ()))
+        } else {
+            Self(None)
+        }
+    }
+}
+
+impl Default for LockGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some(unlock) = self.0 {
+            if leave() {
+                unlock();
+            }
+        }
+    }
 }
 
 pub fn lock<F: FnOnce() -> R, R>(f: F) -> R {
-    if let Some((lock, unlock)) = unsafe { &LOCK } {
-        lock();
+    let _guard = LockGuard::new();
+
+    f()
+}
+
+/// Run the callback `f` named `callback`, returning `failure` if it panics, as per [`PANIC_POLICY`].
+///
+/// The stack holds the lock while calling back, so `f` runs with a [`LockGuard::held`].
+#[cfg(feature = "std")]
+pub(crate) fn guard<R>(callback: &'static str, failure: R, f: impl FnOnce() -> R) -> R {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let _guard = LockGuard::held();
+
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => res,
+        Err(payload) => {
+            #[cfg(feature = "log")]
+            {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("(unknown payload)");
 
-        let res = f();
+                log::error!("Callback {callback} panicked: {message}");
+            }
 
-        unlock();
+            // A copy, so that the hook does not run with the policy locked
+            let policy = *panic_policy();
 
-        res
-    } else {
-        f()
+            match policy {
+                PanicPolicy::Abort => std::process::abort(),
+                PanicPolicy::Report => (),
+                PanicPolicy::Hook(hook) => {
+                    // Neither can the hook unwind into the Matter stack
+                    if catch_unwind(AssertUnwindSafe(|| hook(callback, payload.as_ref()))).is_err()
+                    {
+                        #[cfg(feature = "log")]
+                        log::error!("The panic hook panicked on callback {callback}");
+
+                        std::process::abort();
+                    }
+                }
+            }
+
+            failure
+        }
     }
 }
 
+#[cfg(not(feature = "std"))]
+pub(crate) fn guard<R>(_callback: &'static str, _failure: R, f: impl FnOnce() -> R) -> R {
+    let _guard = LockGuard::held();
+
+    f()
+}
+
 /// # Safety
 ///
 /// Call at the beginning of the program when only the main thread is alive.
 pub unsafe fn initialize(
-    lock: Option<(&'static dyn Fn(), &'static dyn Fn())>,
+    lock: Option<Lock>,
     af: Option<&'static dyn EmberCallback>,
     init: Option<&'static dyn Fn()>,
     provider: Option<&'static dyn ComissionableDataProviderCallback>,
+    #[cfg(feature = "std")] panic_policy: PanicPolicy,
 ) {
     unsafe {
         LOCK = lock;
@@ -92,117 +264,171 @@ pub unsafe fn initialize(
         ACTIONS_PLUGIN_SERVER_INIT = init;
         COMISSIONABLE_DATA_PROVIDER = provider;
 
+        #[cfg(feature = "std")]
+        set_panic_policy(panic_policy);
+
         glue_Initialize();
     }
 }
 
 #[no_mangle]
 extern "C" fn gluecb_emberAfActionsClusterInstantActionCallback(
-    command_obj: *mut chip_app_CommandHandler,
-    command_path: *const chip_app_ConcreteCommandPath,
-    command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+    command_obj: *mut glue_CommandHandler,
+    command_path: *const glue_CommandPath,
+    command_data: *const glue_InstantAction,
 ) -> bool {
-    if let Some(cb) = unsafe { &EMBER } {
-        cb.cluster_instant_action(command_obj, command_path, command_data)
-    } else {
-        true
-    }
+    guard(
+        "gluecb_emberAfActionsClusterInstantActionCallback",
+        false,
+        || {
+            if let Some(cb) = unsafe { EMBER } {
+                cb.cluster_instant_action(command_obj, command_path, command_data)
+            } else {
+                true
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_emberAfExternalAttributeReadCallback(
-    endpoint_id: chip_EndpointId,
-    cluster_id: chip_ClusterId,
-    attribute_meta_data: *const EmberAfAttributeMetadata,
+    endpoint_id: glue_EndpointId,
+    cluster_id: glue_ClusterId,
+    attribute_meta_data: *const glue_AttributeMetadata,
     buffer: *mut u8,
     max_read_length: u16,
-) -> EmberAfStatus {
-    if let Some(cb) = unsafe { &EMBER } {
-        cb.external_attribute_read(
-            endpoint_id,
-            cluster_id,
-            attribute_meta_data,
-            buffer,
-            max_read_length,
-        )
-    } else {
-        EmberAfStatus_EMBER_ZCL_STATUS_FAILURE
-    }
+) -> glue_EmberAfStatus {
+    guard(
+        "gluecb_emberAfExternalAttributeReadCallback",
+        GLUE_EMBER_ZCL_STATUS_FAILURE,
+        || {
+            if let Some(cb) = unsafe { EMBER } {
+                cb.external_attribute_read(
+                    endpoint_id,
+                    cluster_id,
+                    attribute_meta_data,
+                    buffer,
+                    max_read_length,
+                )
+            } else {
+                GLUE_EMBER_ZCL_STATUS_FAILURE
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_emberAfExternalAttributeWriteCallback(
-    endpoint_id: chip_EndpointId,
-    cluster_id: chip_ClusterId,
-    attribute_meta_data: *const EmberAfAttributeMetadata,
+    endpoint_id: glue_EndpointId,
+    cluster_id: glue_ClusterId,
+    attribute_meta_data: *const glue_AttributeMetadata,
     buffer: *const u8,
-) -> EmberAfStatus {
-    if let Some(cb) = unsafe { &EMBER } {
-        cb.external_attribute_write(endpoint_id, cluster_id, attribute_meta_data, buffer)
-    } else {
-        EmberAfStatus_EMBER_ZCL_STATUS_FAILURE
-    }
+) -> glue_EmberAfStatus {
+    guard(
+        "gluecb_emberAfExternalAttributeWriteCallback",
+        GLUE_EMBER_ZCL_STATUS_FAILURE,
+        || {
+            if let Some(cb) = unsafe { EMBER } {
+                cb.external_attribute_write(endpoint_id, cluster_id, attribute_meta_data, buffer)
+            } else {
+                GLUE_EMBER_ZCL_STATUS_FAILURE
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_MatterActionsPluginServerInitCallback() {
-    if let Some(init) = unsafe { &ACTIONS_PLUGIN_SERVER_INIT } {
-        init();
-    }
+    guard("gluecb_MatterActionsPluginServerInitCallback", (), || {
+        if let Some(init) = unsafe { ACTIONS_PLUGIN_SERVER_INIT } {
+            init();
+        }
+    })
 }
 
 #[no_mangle]
 extern "C" fn gluecb_CommissionableDataProvider_GetSetupDiscriminator(
     setup_discriminator: *mut u16,
-) -> CHIP_ERROR {
-    if let Some(cb) = unsafe { &COMISSIONABLE_DATA_PROVIDER } {
-        cb.get_setup_discriminator(setup_discriminator)
-    } else {
-        ChipError::from_code(0x2d).error()
-    }
+) -> glue_ChipError {
+    guard(
+        "gluecb_CommissionableDataProvider_GetSetupDiscriminator",
+        CHIP_ERROR_INTERNAL.error(),
+        || {
+            if let Some(cb) = unsafe { COMISSIONABLE_DATA_PROVIDER } {
+                cb.get_setup_discriminator(setup_discriminator)
+            } else {
+                CHIP_ERROR_NOT_IMPLEMENTED.error()
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_CommissionableDataProvider_GetSpake2pIterationCount(
     iteration_count: *mut u32,
-) -> CHIP_ERROR {
-    if let Some(cb) = unsafe { &COMISSIONABLE_DATA_PROVIDER } {
-        cb.get_spake2p_iteration_count(iteration_count)
-    } else {
-        ChipError::from_code(0x2d).error()
-    }
+) -> glue_ChipError {
+    guard(
+        "gluecb_CommissionableDataProvider_GetSpake2pIterationCount",
+        CHIP_ERROR_INTERNAL.error(),
+        || {
+            if let Some(cb) = unsafe { COMISSIONABLE_DATA_PROVIDER } {
+                cb.get_spake2p_iteration_count(iteration_count)
+            } else {
+                CHIP_ERROR_NOT_IMPLEMENTED.error()
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_CommissionableDataProvider_GetSpake2pSalt(
-    salt_buf: *mut chip_MutableByteSpan,
-) -> CHIP_ERROR {
-    if let Some(cb) = unsafe { &COMISSIONABLE_DATA_PROVIDER } {
-        cb.get_spake2p_salt(salt_buf)
-    } else {
-        ChipError::from_code(0x2d).error()
-    }
+    salt_buf: *mut u8,
+    len: *mut usize,
+) -> glue_ChipError {
+    guard(
+        "gluecb_CommissionableDataProvider_GetSpake2pSalt",
+        CHIP_ERROR_INTERNAL.error(),
+        || {
+            if let Some(cb) = unsafe { COMISSIONABLE_DATA_PROVIDER } {
+                cb.get_spake2p_salt(salt_buf, len)
+            } else {
+                CHIP_ERROR_NOT_IMPLEMENTED.error()
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_CommissionableDataProvider_GetSpake2pVerifier(
-    verifier_buf: *mut chip_MutableByteSpan,
-    out_verifier_len: *mut usize,
-) -> CHIP_ERROR {
-    if let Some(cb) = unsafe { &COMISSIONABLE_DATA_PROVIDER } {
-        cb.get_spake2p_verifier(verifier_buf, out_verifier_len)
-    } else {
-        ChipError::from_code(0x2d).error()
-    }
+    verifier_buf: *mut u8,
+    len: *mut usize,
+) -> glue_ChipError {
+    guard(
+        "gluecb_CommissionableDataProvider_GetSpake2pVerifier",
+        CHIP_ERROR_INTERNAL.error(),
+        || {
+            if let Some(cb) = unsafe { COMISSIONABLE_DATA_PROVIDER } {
+                cb.get_spake2p_verifier(verifier_buf, len)
+            } else {
+                CHIP_ERROR_NOT_IMPLEMENTED.error()
+            }
+        },
+    )
 }
 
 #[no_mangle]
 extern "C" fn gluecb_CommissionableDataProvider_GetSetupPasscode(
     setup_passcode: *mut u32,
-) -> CHIP_ERROR {
-    if let Some(cb) = unsafe { &COMISSIONABLE_DATA_PROVIDER } {
-        cb.get_setup_passcode(setup_passcode)
-    } else {
-        ChipError::from_code(0x2d).error()
-    }
+) -> glue_ChipError {
+    guard(
+        "gluecb_CommissionableDataProvider_GetSetupPasscode",
+        CHIP_ERROR_INTERNAL.error(),
+        || {
+            if let Some(cb) = unsafe { COMISSIONABLE_DATA_PROVIDER } {
+                cb.get_setup_passcode(setup_passcode)
+            } else {
+                CHIP_ERROR_NOT_IMPLEMENTED.error()
+            }
+        },
+    )
 }
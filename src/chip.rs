@@ -2,12 +2,24 @@
 
 use core::borrow::Borrow;
 use core::marker::PhantomData;
-use core::sync::atomic::{AtomicBool, Ordering};
-use core::{ptr, slice};
+use core::mem;
+use core::slice;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
+use crate::clusters::{AttributeId, ClusterId, CommandId};
 use crate::*;
 
 static CTX_TAKEN: AtomicBool = AtomicBool::new(false);
+static CHIP_INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Identifies each [`Chip`], so that a [`StopHandle`] only ever stops the event loop of its own
+static NEXT_GENERATION: AtomicUsize = AtomicUsize::new(1);
+
+/// The generation of the [`Chip`] whose event loop is running - with [`STOPPING`] set while it
+/// is being stopped - or 0
+static EVENT_LOOP: AtomicUsize = AtomicUsize::new(0);
+
+const STOPPING: usize = 1 << (usize::BITS - 1);
 
 pub struct ChipContext(bool, PhantomData<*const ()>);
 
@@ -18,7 +30,7 @@ impl ChipContext {
         {
             Ok(Self(true, PhantomData))
         } else {
-            Err(ChipError::from_code(1)) // TODO: Correct error code
+            Err(CHIP_ERROR_INCORRECT_STATE)
         }
     }
 
@@ -26,54 +38,146 @@ impl ChipContext {
         Self(false, PhantomData)
     }
 
-    pub fn endpoint_updated(&self, id: chip_EndpointId) {
-        unsafe {
-            MatterReportingAttributeChangeCallback3(id);
-        }
+    pub fn endpoint_updated(&self, id: glue_EndpointId) {
+        cb::lock(|| unsafe {
+            glue_ReportEndpointChanged(id);
+        })
     }
 
     pub fn attribute_updated(
         &self,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
-        attribute_id: chip_AttributeId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
+        attribute_id: AttributeId,
     ) {
-        unsafe {
-            MatterReportingAttributeChangeCallback1(endpoint_id, cluster_id, attribute_id);
-        }
+        cb::lock(|| unsafe {
+            glue_ReportAttributeChanged(endpoint_id, cluster_id.raw(), attribute_id.raw());
+        })
     }
 
-    pub fn schedule(&self, work: extern "C" fn(*mut ()), work_ctx: *mut ()) {
-        unsafe {
-            Self::platform_mgr().ScheduleWork(Some(core::mem::transmute(work)), work_ctx as _);
+    /// Schedule `work` to be called with `work_ctx` on the event loop.
+    ///
+    /// # Safety
+    ///
+    /// `work_ctx` must be valid until `work` is called, which might be never if the stack is
+    /// shut down first. `work` runs with the lock held, so it must not lock.
+    pub unsafe fn schedule_raw(&self, work: extern "C" fn(*mut ()), work_ctx: *mut ()) {
+        glue_ScheduleWork(
+            Some(core::mem::transmute::<
+                extern "C" fn(*mut ()),
+                unsafe extern "C" fn(isize),
+            >(work)),
+            work_ctx as _,
+        );
+    }
+
+    /// Schedule `work` to be called on the event loop.
+    ///
+    /// If the event loop is stopped or the stack is shut down before `work` is called, it is
+    /// dropped without being called. Returns [`CHIP_ERROR_INCORRECT_STATE`] while the event loop
+    /// is being stopped.
+    #[cfg(feature = "std")]
+    pub fn schedule<F>(&self, work: F) -> Result<(), ChipError>
+    where
+        F: FnOnce(&ChipContext) + Send + 'static,
+    {
+        schedule_work(Box::new(work), false)
+    }
+
+    /// Schedule `work` to be called on the event loop, and wait for its result.
+    ///
+    /// Returns [`CHIP_ERROR_INCORRECT_STATE`] if `work` is dropped without being called -
+    /// because the event loop is stopped, the stack is shut down or `work` panics - and when
+    /// called from a callback or scheduled work on the event loop thread, with the lock held, or
+    /// when the event loop is not running, where waiting would never end.
+    #[cfg(feature = "std")]
+    pub fn schedule_and_wait<F, R>(&self, work: F) -> Result<R, ChipError>
+    where
+        F: FnOnce(&ChipContext) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        if cb::is_locked_by_current_thread() {
+            return Err(CHIP_ERROR_INCORRECT_STATE);
         }
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+
+        schedule_work(
+            Box::new(move |ctx| {
+                let _ = sender.send(work(ctx));
+            }),
+            true,
+        )?;
+
+        receiver.recv().map_err(|_| CHIP_ERROR_INCORRECT_STATE)
     }
+}
+
+#[cfg(feature = "std")]
+type Work = Box<dyn FnOnce(&ChipContext) + Send>;
 
-    fn platform_mgr() -> &'static mut chip_DeviceLayer_PlatformManager {
-        unsafe { chip_DeviceLayer_PlatformMgr().as_mut() }.unwrap()
+/// The work scheduled with [`ChipContext::schedule`] which has not run yet, by ID
+#[cfg(feature = "std")]
+static PENDING_WORK: std::sync::Mutex<std::collections::BTreeMap<usize, Work>> =
+    std::sync::Mutex::new(std::collections::BTreeMap::new());
+
+#[cfg(feature = "std")]
+static NEXT_WORK_ID: core::sync::atomic::AtomicUsize = core::sync::atomic::AtomicUsize::new(0);
+
+#[cfg(feature = "std")]
+fn pending_work() -> std::sync::MutexGuard<'static, std::collections::BTreeMap<usize, Work>> {
+    PENDING_WORK
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Queue `work` and schedule it on the event loop, failing if the event loop is being stopped -
+/// or is not running, if `running` is set.
+///
+/// The state of the event loop is checked with the queue locked, so that `work` is either
+/// rejected here, or queued before [`stop_event_loop`] drops the queue.
+#[cfg(feature = "std")]
+fn schedule_work(work: Work, running: bool) -> Result<(), ChipError> {
+    if !CHIP_INITIALIZED.load(Ordering::SeqCst) {
+        return Err(CHIP_ERROR_INCORRECT_STATE);
     }
 
-    // fn configuration_mgr() -> &'static mut chip_DeviceLayer_ConfigurationManager {
-    //     unsafe { chip_DeviceLayer_ConfigurationMgr().as_mut() }.unwrap()
-    // }
+    let id = NEXT_WORK_ID.fetch_add(1, Ordering::SeqCst);
+
+    let mut pending = pending_work();
+
+    let state = EVENT_LOOP.load(Ordering::SeqCst);
 
-    fn configuration_mgr_impl() -> &'static mut chip_DeviceLayer_ConfigurationManagerImpl {
-        unsafe { chip_DeviceLayer_ConfigurationManagerImpl_GetDefaultInstance().as_mut() }.unwrap()
+    if state & STOPPING != 0 || running && state == 0 {
+        // Drop `work` after releasing the mutex, as dropping runs arbitrary code
+        drop(pending);
+
+        return Err(CHIP_ERROR_INCORRECT_STATE);
     }
 
-    fn server_init_params() -> &'static mut chip_CommonCaseDeviceServerInitParams {
-        unsafe { glue_CommonCaseDeviceServerInitParams().as_mut() }.unwrap()
+    pending.insert(id, work);
+    drop(pending);
+
+    unsafe {
+        glue_ScheduleWork(Some(run_work), id as _);
     }
 
-    fn server() -> &'static mut chip_Server {
-        unsafe { chip_Server_GetInstance().as_mut() }.unwrap()
+    Ok(())
+}
+
+#[cfg(feature = "std")]
+unsafe extern "C" fn run_work(id: isize) {
+    let work = pending_work().remove(&(id as usize));
+
+    if let Some(work) = work {
+        cb::guard("schedule", (), || work(&ChipContext::internal_new()));
     }
 }
 
 impl Drop for ChipContext {
     fn drop(&mut self) {
         if self.0 {
-            CTX_TAKEN.store(true, Ordering::SeqCst);
+            CTX_TAKEN.store(false, Ordering::SeqCst);
         }
     }
 }
@@ -82,6 +186,9 @@ pub struct ChipConfiguration<'a> {
     pub vendor_id: Option<u16>,
     pub product_id: Option<u16>,
     pub comissionable_data: Option<&'a dyn cb::ComissionableDataProviderCallback>,
+    /// What to do when a callback panics; reporting a failure to the stack by default
+    #[cfg(feature = "std")]
+    pub panic_policy: cb::PanicPolicy,
 }
 
 impl<'a> ChipConfiguration<'a> {
@@ -90,6 +197,8 @@ impl<'a> ChipConfiguration<'a> {
             vendor_id: None,
             product_id: None,
             comissionable_data: None,
+            #[cfg(feature = "std")]
+            panic_policy: cb::PanicPolicy::Report,
         }
     }
 }
@@ -100,7 +209,7 @@ impl<'a> Default for ChipConfiguration<'a> {
     }
 }
 
-pub struct Chip<'a>(&'a ChipContext, PhantomData<&'a ()>);
+pub struct Chip<'a>(&'a ChipContext, usize, PhantomData<&'a ()>);
 
 impl<'a> Chip<'a> {
     pub fn new(
@@ -118,19 +227,74 @@ impl<'a> Chip<'a> {
                 IPv4:     {}
                 TCP:      {}
                 EP-COUNT: {}
-                DEBUG:    {}\n",
-            CONFIG_NETWORK_LAYER_BLE != 0,
-            CHIP_DEVICE_CONFIG_ENABLE_WIFI != 0,
-            CHIP_DEVICE_CONFIG_ENABLE_WPA != 0,
-            CHIP_ENABLE_OPENTHREAD != 0,
-            INET_CONFIG_ENABLE_IPV4 != 0,
-            INET_CONFIG_ENABLE_TCP_ENDPOINT != 0,
-            CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT,
-            CONFIG_IS_DEBUG != 0
+                FABRICS:  {}
+                SESSIONS: {}
+                SUBS:     {}
+                EXCHANGE: {}
+                KVS:      {}
+                DEBUG:    {}
+                LOG-ERR:  {}
+                LOG-PROG: {}
+                LOG-DET:  {}
+                LOG-AUTO: {}\n",
+            GLUE_CONFIG_NETWORK_LAYER_BLE != 0,
+            GLUE_CONFIG_ENABLE_WIFI != 0,
+            GLUE_CONFIG_ENABLE_WPA != 0,
+            GLUE_CONFIG_ENABLE_OPENTHREAD != 0,
+            GLUE_CONFIG_ENABLE_IPV4 != 0,
+            GLUE_CONFIG_ENABLE_TCP_ENDPOINT != 0,
+            GLUE_CONFIG_DYNAMIC_ENDPOINT_COUNT,
+            MAX_FABRICS,
+            SECURE_SESSION_POOL_SIZE,
+            MAX_SUBSCRIPTIONS,
+            MAX_EXCHANGE_CONTEXTS,
+            KVS_PATH.unwrap_or("(default)"),
+            GLUE_CONFIG_IS_DEBUG != 0,
+            ERROR_LOGGING,
+            PROGRESS_LOGGING,
+            DETAIL_LOGGING,
+            AUTOMATION_LOGGING
         );
 
-        chip!(unsafe { chip_Platform_MemoryInit(core::ptr::null_mut(), 0) })?;
-        chip!(unsafe { ChipContext::platform_mgr().InitChipStack() })?;
+        if CHIP_INITIALIZED
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return Err(CHIP_ERROR_INCORRECT_STATE);
+        }
+
+        let mut stage = None;
+
+        if let Err(error) = Self::initialize(callback, conf, &mut stage) {
+            Self::shutdown(stage);
+
+            return Err(error);
+        }
+
+        Ok(Self(
+            context,
+            NEXT_GENERATION.fetch_add(1, Ordering::SeqCst),
+            PhantomData,
+        ))
+    }
+
+    fn initialize(
+        callback: &'a dyn cb::EmberCallback,
+        conf: &ChipConfiguration<'a>,
+        stage: &mut Option<Stage>,
+    ) -> Result<(), ChipError> {
+        #[cfg(feature = "std")]
+        cb::set_panic_policy(conf.panic_policy);
+
+        chip!(unsafe { glue_MemoryInit() }).context("while initializing memory")?;
+        *stage = Some(Stage::Memory);
+
+        chip!(unsafe { glue_InitChipStack() }).context("while initializing the CHIP stack")?;
+        *stage = Some(Stage::Stack);
+
+        unsafe {
+            cb::LOCK = Some((&lock_chip_stack, &unlock_chip_stack));
+        }
 
         unsafe {
             glue_Initialize();
@@ -138,74 +302,249 @@ impl<'a> Chip<'a> {
 
         // TODO: Make conditional
         unsafe {
-            chip_Credentials_SetDeviceAttestationCredentialsProvider(
-                chip_Credentials_Examples_GetExampleDACProvider(),
-            );
+            glue_SetExampleDACProvider();
         }
 
         if let Some(vendor_id) = conf.vendor_id {
-            chip!(unsafe { ChipContext::configuration_mgr_impl().StoreVendorId(vendor_id) })?;
+            chip!(unsafe { glue_StoreVendorId(vendor_id) })
+                .context("while storing the vendor ID")?;
         }
 
         if let Some(product_id) = conf.product_id {
-            chip!(unsafe { ChipContext::configuration_mgr_impl().StoreProductId(product_id) })?;
+            chip!(unsafe { glue_StoreProductId(product_id) })
+                .context("while storing the product ID")?;
         }
 
         unsafe {
-            cb::EMBER = Some(core::mem::transmute(callback));
+            cb::EMBER = Some(core::mem::transmute::<
+                &'a dyn cb::EmberCallback,
+                &'static dyn cb::EmberCallback,
+            >(callback));
         }
 
         if let Some(comissionable_data) = conf.comissionable_data {
             unsafe {
-                cb::COMISSIONABLE_DATA_PROVIDER = Some(core::mem::transmute(comissionable_data));
+                cb::COMISSIONABLE_DATA_PROVIDER = Some(core::mem::transmute::<
+                    &'a dyn cb::ComissionableDataProviderCallback,
+                    &'static dyn cb::ComissionableDataProviderCallback,
+                >(comissionable_data));
             }
         }
 
-        let init_params = ChipContext::server_init_params();
+        chip!(unsafe { glue_InitServer() }).context("while initializing server")?;
+        *stage = Some(Stage::Server);
 
-        chip!(unsafe {
-            chip_CommonCaseDeviceServerInitParams_InitializeStaticResourcesBeforeServerInit(
-                init_params as *mut _ as *mut _,
-            )
-        })?;
-
-        chip!(unsafe { ChipContext::server().Init(init_params as *const _ as *const _) })?;
-
-        StaticEndpoint::<0>::initialize()?;
+        StaticEndpoint::<0>::initialize().context("while initializing the fixed endpoints")?;
 
         // TODO
         //ChipContext::configuration_mgr().LogDeviceConfig();
 
         // TODO
         unsafe {
-            PrintOnboardingCodes(chip_RendezvousInformationFlags {
-                mValue: chip_RendezvousInformationFlag_kOnNetwork,
-                _phantom_0: core::marker::PhantomData,
-            });
+            glue_PrintOnboardingCodes(GLUE_RENDEZVOUS_ON_NETWORK as _);
+        }
+
+        Ok(())
+    }
+
+    /// Tear down what has been brought up of the stack, up to and including `stage`
+    fn shutdown(stage: Option<Stage>) {
+        unsafe {
+            if stage >= Some(Stage::Server) {
+                glue_ShutdownServer();
+            }
+
+            if stage >= Some(Stage::Stack) {
+                glue_ShutdownChipStack();
+            }
+
+            if stage >= Some(Stage::Memory) {
+                glue_MemoryShutdown();
+            }
+
+            cb::LOCK = None;
+            cb::EMBER = None;
+            cb::ACTIONS_PLUGIN_SERVER_INIT = None;
+            cb::COMISSIONABLE_DATA_PROVIDER = None;
         }
 
-        Ok(Self(context, PhantomData))
+        #[cfg(feature = "std")]
+        cb::set_panic_policy(cb::PanicPolicy::Report);
+
+        CHIP_INITIALIZED.store(false, Ordering::SeqCst);
+
+        // Drop the work which did not get to run, after releasing the mutex as dropping runs arbitrary code
+        #[cfg(feature = "std")]
+        let _work = mem::take(&mut *pending_work());
     }
 
     pub fn context(&self) -> &ChipContext {
-        &self.0
+        self.0
+    }
+
+    /// Get a handle to stop the event loop, e.g. from another thread or a callback
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle(self.1)
     }
 
+    /// Run the event loop on the current thread, until it is stopped with a [`StopHandle`]
     pub fn run(&mut self) {
+        EVENT_LOOP.store(self.1, Ordering::SeqCst);
+
         unsafe {
-            ChipContext::platform_mgr().RunEventLoop();
+            glue_RunEventLoop();
+        }
+
+        // Once the stop which made the event loop return is done, if any
+        stop_event_loop(self.1, true, || ());
+    }
+
+    /// Run the event loop on a thread of its own, until the returned handle is dropped
+    pub fn start(&mut self) -> Result<ChipHandle<'_>, ChipError> {
+        EVENT_LOOP.store(self.1, Ordering::SeqCst);
+
+        if let Err(error) = chip!(unsafe { glue_StartEventLoopTask() }) {
+            EVENT_LOOP.store(0, Ordering::SeqCst);
+
+            return Err(error.context("while starting the event loop"));
         }
+
+        Ok(ChipHandle(self.1, PhantomData))
+    }
+}
+
+/// Stop the event loop of the [`Chip`] of `generation` with `stop`, if it is running.
+///
+/// Returns `None` if the event loop is not running, or if another thread is stopping it already,
+/// in which case this first waits for that thread to be done if `wait` is set.
+fn stop_event_loop<R>(generation: usize, wait: bool, stop: impl FnOnce() -> R) -> Option<R> {
+    loop {
+        match EVENT_LOOP.compare_exchange(
+            generation,
+            generation | STOPPING,
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        ) {
+            Ok(_) => break,
+            Err(state) if wait && state == generation | STOPPING => {
+                #[cfg(feature = "std")]
+                std::thread::yield_now();
+
+                #[cfg(not(feature = "std"))]
+                core::hint::spin_loop();
+            }
+            Err(_) => return None,
+        }
+    }
+
+    let result = stop();
+
+    // Drop the work which did not get to run - waking up anyone waiting for it - after releasing
+    // the mutex, as dropping runs arbitrary code
+    #[cfg(feature = "std")]
+    let _work = mem::take(&mut *pending_work());
+
+    EVENT_LOOP.store(0, Ordering::SeqCst);
+
+    Some(result)
+}
+
+fn lock_chip_stack() {
+    unsafe {
+        glue_LockChipStack();
+    }
+}
+
+fn unlock_chip_stack() {
+    unsafe {
+        glue_UnlockChipStack();
     }
 }
 
+/// Dropping stops the event loop, if it is still running, and shuts the stack down,
+/// after which a new [`Chip`] can be created.
 impl<'a> Drop for Chip<'a> {
     fn drop(&mut self) {
-        unsafe {
-            cb::LOCK = None;
-            cb::EMBER = None;
-            cb::ACTIONS_PLUGIN_SERVER_INIT = None;
-            cb::COMISSIONABLE_DATA_PROVIDER = None;
+        let _result = stop_event_loop(self.1, true, || chip!(unsafe { glue_StopEventLoopTask() }));
+
+        #[cfg(feature = "log")]
+        if let Some(Err(error)) = _result {
+            log::warn!("Stopping the event loop failed: {error}");
         }
+
+        Self::shutdown(Some(Stage::Server));
+    }
+}
+
+/// A handle to the event loop started with [`Chip::start`], which can be shared with other threads.
+///
+/// Dropping the handle stops the event loop and waits for its thread to exit.
+pub struct ChipHandle<'c>(usize, PhantomData<&'c ()>);
+
+impl<'c> ChipHandle<'c> {
+    /// Call `f` with the stack locked, e.g. to report attribute changes or to enable endpoints
+    pub fn lock<F: FnOnce(&ChipContext) -> R, R>(&self, f: F) -> R {
+        lock(f)
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle(self.0)
+    }
+
+    /// See [`ChipContext::schedule`]
+    #[cfg(feature = "std")]
+    pub fn schedule<F>(&self, work: F) -> Result<(), ChipError>
+    where
+        F: FnOnce(&ChipContext) + Send + 'static,
+    {
+        ChipContext::internal_new().schedule(work)
+    }
+
+    /// See [`ChipContext::schedule_and_wait`]
+    #[cfg(feature = "std")]
+    pub fn schedule_and_wait<F, R>(&self, work: F) -> Result<R, ChipError>
+    where
+        F: FnOnce(&ChipContext) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        ChipContext::internal_new().schedule_and_wait(work)
+    }
+}
+
+impl<'c> Drop for ChipHandle<'c> {
+    fn drop(&mut self) {
+        let _result = stop_event_loop(self.0, true, || chip!(unsafe { glue_StopEventLoopTask() }));
+
+        #[cfg(feature = "log")]
+        if let Some(Err(error)) = _result {
+            log::warn!("Stopping the event loop failed: {error}");
+        }
+    }
+}
+
+/// The stages of bringing up the stack in [`Chip::new`], in order
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Stage {
+    Memory,
+    Stack,
+    Server,
+}
+
+/// A handle to stop the event loop of the [`Chip`], making [`Chip::run`] return.
+///
+/// Can be sent to other threads, and outlive the [`Chip`], in which case stopping does nothing,
+/// even if a new [`Chip`] is created in the meantime.
+#[derive(Copy, Clone, Debug)]
+pub struct StopHandle(usize);
+
+impl StopHandle {
+    /// Stop the event loop.
+    ///
+    /// Returns [`CHIP_ERROR_INCORRECT_STATE`] if the event loop is not running, e.g. because
+    /// it is already stopped or being stopped, or the [`Chip`] is shut down.
+    pub fn stop(&self) -> Result<(), ChipError> {
+        stop_event_loop(self.0, false, || chip!(unsafe { glue_StopEventLoopTask() }))
+            .unwrap_or(Err(CHIP_ERROR_INCORRECT_STATE))
     }
 }
 
@@ -213,9 +552,9 @@ pub trait EmberCallback {
     fn invoke(
         &self,
         _ctx: &ChipContext,
-        _command_obj: *mut chip_app_CommandHandler,
-        _command_path: *const chip_app_ConcreteCommandPath,
-        _command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+        _command_obj: *mut glue_CommandHandler,
+        _command_path: *const glue_CommandPath,
+        _command_data: *const glue_InstantAction,
     ) -> bool {
         false
     }
@@ -223,8 +562,8 @@ pub trait EmberCallback {
     fn read(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &mut [u8],
     ) -> Result<(), EmberAfError>;
@@ -232,8 +571,8 @@ pub trait EmberCallback {
     fn write(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &[u8],
     ) -> Result<(), EmberAfError>;
@@ -246,9 +585,9 @@ where
     fn invoke(
         &self,
         ctx: &ChipContext,
-        command_obj: *mut chip_app_CommandHandler,
-        command_path: *const chip_app_ConcreteCommandPath,
-        command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+        command_obj: *mut glue_CommandHandler,
+        command_path: *const glue_CommandPath,
+        command_data: *const glue_InstantAction,
     ) -> bool {
         (*self).invoke(ctx, command_obj, command_path, command_data)
     }
@@ -256,8 +595,8 @@ where
     fn read(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &mut [u8],
     ) -> Result<(), EmberAfError> {
@@ -267,8 +606,8 @@ where
     fn write(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &[u8],
     ) -> Result<(), EmberAfError> {
@@ -283,9 +622,9 @@ where
     fn invoke(
         &self,
         ctx: &ChipContext,
-        command_obj: *mut chip_app_CommandHandler,
-        command_path: *const chip_app_ConcreteCommandPath,
-        command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+        command_obj: *mut glue_CommandHandler,
+        command_path: *const glue_CommandPath,
+        command_data: *const glue_InstantAction,
     ) -> bool {
         (**self).invoke(ctx, command_obj, command_path, command_data)
     }
@@ -293,8 +632,8 @@ where
     fn read(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &mut [u8],
     ) -> Result<(), EmberAfError> {
@@ -304,8 +643,8 @@ where
     fn write(
         &self,
         ctx: &ChipContext,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
+        endpoint_id: glue_EndpointId,
+        cluster_id: ClusterId,
         attribute: &Attribute,
         buffer: &[u8],
     ) -> Result<(), EmberAfError> {
@@ -319,9 +658,9 @@ where
 {
     fn cluster_instant_action(
         &self,
-        command_obj: *mut chip_app_CommandHandler,
-        command_path: *const chip_app_ConcreteCommandPath,
-        command_data: *const chip_app_Clusters_Actions_Commands_InstantAction_DecodableType,
+        command_obj: *mut glue_CommandHandler,
+        command_path: *const glue_CommandPath,
+        command_data: *const glue_InstantAction,
     ) -> bool {
         EmberCallback::invoke(
             &self,
@@ -332,40 +671,49 @@ where
         )
     }
 
+    // The pointers are the ones the SDK passes to the attribute callbacks of `cb`
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn external_attribute_read(
         &self,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
-        attribute_meta_data: *const EmberAfAttributeMetadata,
+        endpoint_id: glue_EndpointId,
+        cluster_id: glue_ClusterId,
+        attribute_meta_data: *const glue_AttributeMetadata,
         buffer: *mut u8,
         max_read_length: u16,
-    ) -> EmberAfStatus {
-        let attribute = unsafe { (attribute_meta_data as *const Attribute).as_ref() }.unwrap();
+    ) -> glue_EmberAfStatus {
+        let Some(attribute) = (unsafe { (attribute_meta_data as *const Attribute).as_ref() })
+        else {
+            return GLUE_EMBER_ZCL_STATUS_FAILURE;
+        };
 
         EmberAfError::to_raw(EmberCallback::read(
             self,
             &ChipContext::internal_new(),
             endpoint_id,
-            cluster_id,
+            ClusterId(cluster_id),
             attribute,
             unsafe { slice::from_raw_parts_mut(buffer, max_read_length as _) },
         ))
     }
 
+    #[allow(clippy::not_unsafe_ptr_arg_deref)]
     fn external_attribute_write(
         &self,
-        endpoint_id: chip_EndpointId,
-        cluster_id: chip_ClusterId,
-        attribute_meta_data: *const EmberAfAttributeMetadata,
+        endpoint_id: glue_EndpointId,
+        cluster_id: glue_ClusterId,
+        attribute_meta_data: *const glue_AttributeMetadata,
         buffer: *const u8,
-    ) -> EmberAfStatus {
-        let attribute = unsafe { (attribute_meta_data as *const Attribute).as_ref() }.unwrap();
+    ) -> glue_EmberAfStatus {
+        let Some(attribute) = (unsafe { (attribute_meta_data as *const Attribute).as_ref() })
+        else {
+            return GLUE_EMBER_ZCL_STATUS_FAILURE;
+        };
 
         EmberAfError::to_raw(EmberCallback::write(
             self,
             &ChipContext::internal_new(),
             endpoint_id,
-            cluster_id,
+            ClusterId(cluster_id),
             attribute,
             unsafe { slice::from_raw_parts(buffer, attribute.size()) },
         ))
@@ -382,49 +730,57 @@ pub struct ComissionableData<'a> {
 }
 
 impl<'a> cb::ComissionableDataProviderCallback for ComissionableData<'a> {
-    fn get_setup_discriminator(&self, setup_discriminator: *mut u16) -> CHIP_ERROR {
-        *unsafe { setup_discriminator.as_mut() }.unwrap() = self.setup_discriminator;
-        ChipError::from_code(0).error()
+    fn get_setup_discriminator(&self, setup_discriminator: *mut u16) -> glue_ChipError {
+        copy_to(self.setup_discriminator, setup_discriminator)
     }
 
-    fn get_setup_passcode(&self, setup_passcode: *mut u32) -> CHIP_ERROR {
-        *unsafe { setup_passcode.as_mut() }.unwrap() = self.setup_passcode;
-        ChipError::from_code(0).error()
+    fn get_setup_passcode(&self, setup_passcode: *mut u32) -> glue_ChipError {
+        copy_to(self.setup_passcode, setup_passcode)
     }
 
-    fn get_spake2p_iteration_count(&self, iteration_count: *mut u32) -> CHIP_ERROR {
-        *unsafe { iteration_count.as_mut() }.unwrap() = self.spake2p_iteration_count;
-        ChipError::from_code(0).error()
+    fn get_spake2p_iteration_count(&self, iteration_count: *mut u32) -> glue_ChipError {
+        copy_to(self.spake2p_iteration_count, iteration_count)
     }
 
-    fn get_spake2p_salt(&self, salt_buf: *mut chip_MutableByteSpan) -> CHIP_ERROR {
-        let salt_buf = unsafe { salt_buf.as_mut() }.unwrap();
-        let salt_data_buf =
-            unsafe { core::slice::from_raw_parts_mut(salt_buf.mDataBuf, self.spake2p_salt.len()) };
-
-        salt_data_buf.copy_from_slice(self.spake2p_salt);
-        salt_buf.mDataLen = self.spake2p_salt.len();
+    fn get_spake2p_salt(&self, salt_buf: *mut u8, len: *mut usize) -> glue_ChipError {
+        copy_to_buf(self.spake2p_salt, salt_buf, len)
+    }
 
-        ChipError::from_code(0).error()
+    fn get_spake2p_verifier(&self, verifier_buf: *mut u8, len: *mut usize) -> glue_ChipError {
+        copy_to_buf(self.spake2p_verifier, verifier_buf, len)
     }
+}
 
-    fn get_spake2p_verifier(
-        &self,
-        verifier_buf: *mut chip_MutableByteSpan,
-        out_verifier_len: *mut usize,
-    ) -> CHIP_ERROR {
-        let verifier_buf = unsafe { verifier_buf.as_mut() }.unwrap();
-        let verifier_data_buf = unsafe {
-            core::slice::from_raw_parts_mut(verifier_buf.mDataBuf, self.spake2p_verifier.len())
-        };
+fn copy_to<T>(value: T, dest: *mut T) -> glue_ChipError {
+    let result = if let Some(dest) = unsafe { dest.as_mut() } {
+        *dest = value;
+
+        Ok(())
+    } else {
+        Err(CHIP_ERROR_INVALID_ARGUMENT)
+    };
 
-        verifier_data_buf.copy_from_slice(self.spake2p_verifier);
-        verifier_buf.mDataLen = self.spake2p_verifier.len();
+    ChipError::to_raw(result)
+}
 
-        *unsafe { out_verifier_len.as_mut() }.unwrap() = self.spake2p_verifier.len() as _;
+fn copy_to_buf(data: &[u8], buf: *mut u8, len: *mut usize) -> glue_ChipError {
+    let Some(len) = (unsafe { len.as_mut() }) else {
+        return CHIP_ERROR_INVALID_ARGUMENT.error();
+    };
 
-        ChipError::from_code(0).error()
-    }
+    let result = if data.len() > *len {
+        Err(CHIP_ERROR_BUFFER_TOO_SMALL)
+    } else if buf.is_null() && !data.is_empty() {
+        Err(CHIP_ERROR_INVALID_ARGUMENT)
+    } else {
+        unsafe { slice::from_raw_parts_mut(buf, data.len()) }.copy_from_slice(data);
+
+        Ok(())
+    };
+
+    *len = data.len();
+
+    ChipError::to_raw(result)
 }
 
 pub static TEST_COMISSIONABLE_DATA: ComissionableData<'static> = ComissionableData {
@@ -447,126 +803,146 @@ pub fn lock<F: FnOnce(&ChipContext) -> R, R>(f: F) -> R {
     cb::lock(|| f(&ChipContext::internal_new()))
 }
 
-pub const ENDPOINT_ID_RANGE_START: chip_EndpointId = FIXED_ENDPOINT_COUNT as _;
-pub const ENDPOINT_COUNT: usize = CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT as _;
+pub const ENDPOINT_ID_RANGE_START: glue_EndpointId = FIXED_ENDPOINT_COUNT;
+pub const ENDPOINT_COUNT: usize = GLUE_CONFIG_DYNAMIC_ENDPOINT_COUNT as _;
+
+pub const MAX_FABRICS: usize = GLUE_CONFIG_MAX_FABRICS as _;
+pub const SECURE_SESSION_POOL_SIZE: usize = GLUE_CONFIG_SECURE_SESSION_POOL_SIZE as _;
+pub const MAX_SUBSCRIPTIONS: usize = GLUE_CONFIG_MAX_SUBSCRIPTIONS as _;
+pub const MAX_EXCHANGE_CONTEXTS: usize = GLUE_CONFIG_MAX_EXCHANGE_CONTEXTS as _;
+
+/// The path of the key-value store, if overridden with the `CHIP_KVS_PATH` build variable
+pub const KVS_PATH: Option<&str> = option_env!("CHIP_CONFIG_KVS_PATH");
 
-pub const ROOT_NODE: StaticEndpoint<0> = StaticEndpoint;
-pub const BRIDGE_NODE: StaticEndpoint<1> = StaticEndpoint;
+/// The log categories of the SDK compiled in, as selected with the `CHIP_LOGGING` build variable
+pub const ERROR_LOGGING: bool = GLUE_CONFIG_ERROR_LOGGING != 0;
+pub const PROGRESS_LOGGING: bool = GLUE_CONFIG_PROGRESS_LOGGING != 0;
+pub const DETAIL_LOGGING: bool = GLUE_CONFIG_DETAIL_LOGGING != 0;
+pub const AUTOMATION_LOGGING: bool = GLUE_CONFIG_AUTOMATION_LOGGING != 0;
 
-const TEMPLATE_NODE: StaticEndpoint<2> = StaticEndpoint;
+include!(env!("GENERATED_ENDPOINTS_FILE"));
 
-pub struct StaticEndpoint<const ID: chip_EndpointId>;
+pub struct StaticEndpoint<const ID: glue_EndpointId>;
 
-impl<const ID: chip_EndpointId> StaticEndpoint<ID> {
-    pub const fn id(&self) -> chip_EndpointId {
+impl<const ID: glue_EndpointId> StaticEndpoint<ID> {
+    pub const fn id(&self) -> glue_EndpointId {
         ID
     }
 
     pub fn initialize() -> Result<(), ChipError> {
-        static ROOT_DEVICE_TYPES: &[DeviceType] = &[DeviceType::of(0x0016)]; // taken from chip-devices.xml
-        ROOT_NODE.initialize_ep(ROOT_DEVICE_TYPES)?;
+        // Device types of the fixed endpoints, as per the data model CHIP was built with
+        for (id, device_types) in FIXED_ENDPOINTS {
+            set_device_types(*id, device_types)?;
+        }
 
-        //
-        // A bridge has root node device type on EP0 and aggregate node device type (bridge) at EP1
-        //
-        static BRIDGE_NODE_DEVICE_TYPES: &[DeviceType] = &[DeviceType::of(0x000e)]; // taken from chip-devices.xml
-        BRIDGE_NODE.initialize_ep(BRIDGE_NODE_DEVICE_TYPES)?;
+        // `enable_endpoint` only fails with an error status, never with a success one
+        let chip_error = |error: EmberAfError| -> ChipError { ImStatus::from(error).into() };
 
-        // Disable last fixed endpoint, which is used as a placeholder for all of the
+        // Disable the fixed endpoints which are only used as a placeholder for all of the
         // supported clusters so that ZAP will generate the requisite code.
-        TEMPLATE_NODE.enable(false);
+        for id in TEMPLATE_ENDPOINTS {
+            enable_endpoint(*id, false).map_err(chip_error)?;
+        }
 
         // Disable the bridge EP; users can re-enable
-        BRIDGE_NODE.enable(false);
+        for id in BRIDGE_ENDPOINTS {
+            enable_endpoint(*id, false).map_err(chip_error)?;
+        }
 
         Ok(())
     }
 
-    fn initialize_ep(&self, device_types: &'static [DeviceType]) -> Result<(), ChipError> {
-        lock(|_| {
-            chip!(unsafe {
-                emberAfSetDeviceTypeList(
-                    self.id(),
-                    chip_Span {
-                        mDataBuf: device_types.as_ptr() as *mut _,
-                        mDataLen: device_types.len(),
-                        _phantom_0: core::marker::PhantomData,
-                    },
-                )
-            })?;
-
-            Ok(())
-        })
+    pub fn enable(&self, enable: bool) -> Result<(), EmberAfError> {
+        enable_endpoint(self.id(), enable)
     }
+}
 
-    pub fn enable(&self, enable: bool) {
-        lock(|_| unsafe {
-            emberAfEndpointEnableDisable(self.id(), enable);
+fn set_device_types(
+    id: glue_EndpointId,
+    device_types: DeviceTypes<'static>,
+) -> Result<(), ChipError> {
+    lock(|_| {
+        chip!(unsafe {
+            glue_SetDeviceTypeList(id, device_types.as_ptr() as *const _, device_types.len())
         })
+        .context("while setting the device types of an endpoint")?;
+
+        Ok(())
+    })
+}
+
+fn enable_endpoint(id: glue_EndpointId, enable: bool) -> Result<(), EmberAfError> {
+    if lock(|_| unsafe { glue_EndpointEnableDisable(id, enable) }) {
+        Ok(())
+    } else {
+        Err(EmberAfError::from(
+            GLUE_EMBER_ZCL_STATUS_UNSUPPORTED_ENDPOINT,
+        ))
     }
 }
 
 #[derive(Debug)]
-pub struct EndpointRegistration<'r>(chip_EndpointId, PhantomData<&'r ()>);
+pub struct EndpointRegistration<'r>(glue_EndpointId, PhantomData<&'r ()>);
 
 impl<'r> EndpointRegistration<'r> {
-    pub fn new<const PARENT_ID: chip_EndpointId>(
-        id: chip_EndpointId,
+    pub fn new<const PARENT_ID: glue_EndpointId>(
+        id: glue_EndpointId,
         device_types: DeviceTypes<'r>,
         endpoint_type: &'r EndpointType,
-        data_versions: &'r mut [chip_DataVersion],
+        data_versions: &'r mut [glue_DataVersion],
         parent: StaticEndpoint<PARENT_ID>,
     ) -> Result<Self, EmberAfError> {
         lock(|_| {
             if EndpointRegistration::find_index(id).is_some() {
-                Err(EmberAfError::from(
-                    EmberAfStatus_EMBER_ZCL_STATUS_DUPLICATE_EXISTS,
-                ))
-            } else if let Some(index) = EndpointRegistration::find_index(chip_kInvalidEndpointId) {
+                Err(EmberAfError::from(GLUE_EMBER_ZCL_STATUS_DUPLICATE_EXISTS))
+            } else if let Some(index) =
+                EndpointRegistration::find_index(GLUE_INVALID_ENDPOINT_ID as _)
+            {
                 #[cfg(feature = "log")]
                 log::info!("Registering EP {id} at index {index}");
 
                 ember!(unsafe {
-                    emberAfSetDynamicEndpoint(
-                        index - FIXED_ENDPOINT_COUNT as u16,
+                    glue_SetDynamicEndpoint(
+                        index - FIXED_ENDPOINT_COUNT,
                         id,
-                        endpoint_type as *const _ as *const _,
-                        &chip_Span {
-                            mDataBuf: data_versions.as_ptr() as *mut _,
-                            mDataLen: data_versions.len(),
-                            _phantom_0: core::marker::PhantomData,
-                        },
-                        chip_Span {
-                            mDataBuf: device_types.as_ptr() as *mut _,
-                            mDataLen: device_types.len(),
-                            _phantom_0: core::marker::PhantomData,
-                        },
+                        endpoint_type.raw(),
+                        data_versions.as_mut_ptr(),
+                        data_versions.len(),
+                        device_types.as_ptr() as *const _,
+                        device_types.len(),
                         parent.borrow().id(),
                     )
                 })?;
 
                 Ok(EndpointRegistration(id, PhantomData))
             } else {
-                Err(EmberAfError::from(
-                    EmberAfStatus_EMBER_ZCL_STATUS_RESOURCE_EXHAUSTED,
-                ))
+                Err(EmberAfError::from(GLUE_EMBER_ZCL_STATUS_RESOURCE_EXHAUSTED))
             }
         })
     }
 
-    pub fn enable(&self, _ctx: &ChipContext, enable: bool) {
+    pub fn enable(&self, _ctx: &ChipContext, enable: bool) -> Result<(), EmberAfError> {
         #[cfg(feature = "log")]
         log::info!("Setting enabled state for EP {} to {}", self.id(), enable);
 
-        lock(|_| unsafe {
-            emberAfEndpointEnableDisable(
-                emberAfEndpointFromIndex(self.index().unwrap() as _),
-                enable,
-            );
-        });
+        lock(|_| {
+            self.index()
+                .ok_or(EmberAfError::from(GLUE_EMBER_ZCL_STATUS_NOT_FOUND))?;
+
+            enable_endpoint(self.id(), enable)
+        })
+    }
+
+    /// Unregister the endpoint, which is otherwise done - ignoring errors - when the registration is dropped
+    pub fn unregister(self) -> Result<(), EmberAfError> {
+        let result = self.clear();
+
+        mem::forget(self);
+
+        result
     }
 
-    pub const fn id(&self) -> chip_EndpointId {
+    pub const fn id(&self) -> glue_EndpointId {
         self.0
     }
 
@@ -574,53 +950,62 @@ impl<'r> EndpointRegistration<'r> {
         Self::find_index(self.0)
     }
 
-    fn find_index(id: chip_EndpointId) -> Option<u16> {
+    fn clear(&self) -> Result<(), EmberAfError> {
         lock(|_| {
-            for index in
-                0..(FIXED_ENDPOINT_COUNT + CHIP_DEVICE_CONFIG_DYNAMIC_ENDPOINT_COUNT) as u16
-            {
-                if unsafe { emberAfEndpointFromIndex(index) } == id {
-                    return Some(index as _);
-                }
+            let index = self
+                .index()
+                .ok_or(EmberAfError::from(GLUE_EMBER_ZCL_STATUS_NOT_FOUND))?;
+
+            #[cfg(feature = "log")]
+            log::info!("Unregistering EP {} from index {index}", self.id());
+
+            if unsafe { glue_ClearDynamicEndpoint(index - FIXED_ENDPOINT_COUNT) } == self.id() {
+                Ok(())
+            } else {
+                Err(EmberAfError::from(GLUE_EMBER_ZCL_STATUS_FAILURE))
             }
+        })
+    }
 
-            None
+    fn find_index(id: glue_EndpointId) -> Option<u16> {
+        lock(|_| {
+            (0..FIXED_ENDPOINT_COUNT + GLUE_CONFIG_DYNAMIC_ENDPOINT_COUNT as u16)
+                .find(|index| unsafe { glue_EndpointFromIndex(*index) } == id)
         })
     }
 }
 
 impl<'r> Drop for EndpointRegistration<'r> {
     fn drop(&mut self) {
-        lock(|_| {
-            let index = self.index().unwrap();
+        let _result = self.clear();
 
-            #[cfg(feature = "log")]
-            log::info!("Unegistering EP {} from index {index}", self.id());
-
-            unsafe {
-                emberAfClearDynamicEndpoint(index - FIXED_ENDPOINT_COUNT as u16);
-            }
-        });
+        #[cfg(feature = "log")]
+        if let Err(error) = _result {
+            log::warn!("Unregistering EP {} failed: {error}", self.id());
+        }
     }
 }
 
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct EndpointType<'a, 'c>(EmberAfEndpointType, PhantomData<&'a [&'c ()]>);
+pub struct EndpointType<'a, 'c>(glue_EndpointType, PhantomData<&'a [&'c ()]>);
 
 impl<'a, 'c> EndpointType<'a, 'c> {
     pub const fn new(clusters: &'a [Cluster<'c>]) -> Self {
         Self(
-            EmberAfEndpointType {
+            glue_EndpointType {
                 cluster: clusters.as_ptr() as _,
                 clusterCount: clusters.len() as _,
-                endpointSize: 0,
             },
             PhantomData,
         )
     }
 
-    pub fn clusters(&self) -> ClusterIterator {
+    pub const fn raw(&self) -> &glue_EndpointType {
+        &self.0
+    }
+
+    pub fn clusters(&self) -> ClusterIterator<'_, 'a, 'c> {
         ClusterIterator { ep: self, index: 0 }
     }
 }
@@ -655,11 +1040,15 @@ impl<'i, 'a, 'c> Iterator for ClusterIterator<'i, 'a, 'c> {
 
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct DeviceType(EmberAfDeviceType);
+pub struct DeviceType(glue_DeviceType);
 
 impl DeviceType {
-    pub const fn id(&self) -> EmberAfDeviceType {
-        self.0
+    pub const fn id(&self) -> u16 {
+        self.0.deviceId
+    }
+
+    pub const fn version(&self) -> u8 {
+        self.0.deviceVersion
     }
 
     pub const fn of(id: u16) -> Self {
@@ -667,7 +1056,7 @@ impl DeviceType {
     }
 
     pub const fn new(id: u16, version: u8) -> Self {
-        Self(EmberAfDeviceType {
+        Self(glue_DeviceType {
             deviceId: id,
             deviceVersion: version,
         })
@@ -678,23 +1067,20 @@ pub type DeviceTypes<'a> = &'a [DeviceType];
 
 #[repr(transparent)]
 #[derive(Debug)]
-pub struct Cluster<'a>(EmberAfCluster, PhantomData<&'a ()>);
+pub struct Cluster<'a>(glue_Cluster, PhantomData<&'a ()>);
 
 impl<'a> Cluster<'a> {
     pub const fn new(
-        id: chip_ClusterId,
+        id: ClusterId,
         attributes: &'a [Attribute],
         accepted_commands: Option<&'a [Command]>,
         generated_commands: Option<&'a [Command]>,
     ) -> Self {
         Self(
-            EmberAfCluster {
-                clusterId: id,
+            glue_Cluster {
+                clusterId: id.0,
                 attributes: attributes.as_ptr() as _,
                 attributeCount: attributes.len() as _,
-                clusterSize: 0,
-                mask: CLUSTER_MASK_SERVER as _,
-                functions: ptr::null(),
                 acceptedCommandList: if let Some(accepted_commands) = accepted_commands {
                     accepted_commands.as_ptr() as _
                 } else {
@@ -710,15 +1096,15 @@ impl<'a> Cluster<'a> {
         )
     }
 
-    pub const fn raw(&self) -> &EmberAfCluster {
+    pub const fn raw(&self) -> &glue_Cluster {
         &self.0
     }
 
-    pub const fn id(&self) -> chip_ClusterId {
-        self.0.clusterId
+    pub const fn id(&self) -> ClusterId {
+        ClusterId(self.0.clusterId)
     }
 
-    pub fn attributes(&self) -> AttributeIterator {
+    pub fn attributes(&self) -> AttributeIterator<'_, 'a> {
         AttributeIterator {
             cluster: self,
             index: 0,
@@ -861,37 +1247,31 @@ impl<'i, 'a> Iterator for AttributeIterator<'i, 'a> {
 }
 
 #[repr(transparent)]
-pub struct Attribute(EmberAfAttributeMetadata);
+pub struct Attribute(glue_AttributeMetadata);
 
 unsafe impl Send for Attribute {}
 unsafe impl Sync for Attribute {}
 
 impl Attribute {
-    pub const fn new(
-        id: chip_AttributeId,
-        r#type: EmberAfAttributeType,
-        size: u16,
-        mask: EmberAfAttributeMask,
-        //default_value: EmberAfDefaultOrMinMaxAttributeValue,
-    ) -> Self {
-        Self(EmberAfAttributeMetadata {
-            attributeId: id,
+    pub const fn new(id: AttributeId, r#type: u8, size: u16, mask: u8) -> Self {
+        // Always with external storage, see `glue_SetDynamicEndpoint`
+        Self(glue_AttributeMetadata {
+            attributeId: id.0,
             attributeType: r#type,
             size,
-            mask: mask | ATTRIBUTE_MASK_EXTERNAL_STORAGE as u8,
-            defaultValue: EmberAfDefaultOrMinMaxAttributeValue { defaultValue: 0 },
+            mask,
         })
     }
 
-    pub const fn raw(&self) -> &EmberAfAttributeMetadata {
+    pub const fn raw(&self) -> &glue_AttributeMetadata {
         &self.0
     }
 
-    pub const fn id(&self) -> chip_AttributeId {
-        self.0.attributeId
+    pub const fn id(&self) -> AttributeId {
+        AttributeId(self.0.attributeId)
     }
 
-    pub const fn attr_type(&self) -> EmberAfAttributeType {
+    pub const fn attr_type(&self) -> u8 {
         self.0.attributeType
     }
 
@@ -899,63 +1279,63 @@ impl Attribute {
         self.0.size as _
     }
 
-    pub const fn boolean(id: chip_AttributeId) -> Self {
+    pub const fn boolean(id: AttributeId) -> Self {
         Self::new(id, ZCL_BOOLEAN_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn b8(id: chip_AttributeId) -> Self {
+    pub const fn b8(id: AttributeId) -> Self {
         Self::new(id, ZCL_BITMAP8_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn b16(id: chip_AttributeId) -> Self {
+    pub const fn b16(id: AttributeId) -> Self {
         Self::new(id, ZCL_BITMAP16_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn b32(id: chip_AttributeId) -> Self {
+    pub const fn b32(id: AttributeId) -> Self {
         Self::new(id, ZCL_BITMAP32_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn b64(id: chip_AttributeId) -> Self {
+    pub const fn b64(id: AttributeId) -> Self {
         Self::new(id, ZCL_BITMAP64_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn u8(id: chip_AttributeId) -> Self {
+    pub const fn u8(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT8U_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn u16(id: chip_AttributeId) -> Self {
+    pub const fn u16(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT16U_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn u32(id: chip_AttributeId) -> Self {
+    pub const fn u32(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT32U_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn u64(id: chip_AttributeId) -> Self {
+    pub const fn u64(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT64U_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn i8(id: chip_AttributeId) -> Self {
+    pub const fn i8(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT8S_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn i16(id: chip_AttributeId) -> Self {
+    pub const fn i16(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT16S_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn i32(id: chip_AttributeId) -> Self {
+    pub const fn i32(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT32S_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn i64(id: chip_AttributeId) -> Self {
+    pub const fn i64(id: AttributeId) -> Self {
         Self::new(id, ZCL_INT64S_ATTRIBUTE_TYPE as _, 1, 0)
     }
 
-    pub const fn string(id: chip_AttributeId) -> Self {
+    pub const fn string(id: AttributeId) -> Self {
         Self::new(id, ZCL_CHAR_STRING_ATTRIBUTE_TYPE as _, 32, 0)
     }
 
-    pub const fn array(id: chip_AttributeId) -> Self {
+    pub const fn array(id: AttributeId) -> Self {
         Self::new(id, ZCL_ARRAY_ATTRIBUTE_TYPE as _, 254, 0)
     }
 }
@@ -964,16 +1344,16 @@ pub type Attributes<'a> = &'a [Attribute];
 
 #[derive(Debug)]
 #[repr(transparent)]
-pub struct Command(chip_CommandId);
+pub struct Command(CommandId);
 
 impl Command {
-    pub const END: Command = Command(0xFFFF_FFFF);
+    pub const END: Command = Command(CommandId(GLUE_INVALID_COMMAND_ID));
 
-    pub const fn id(&self) -> chip_CommandId {
+    pub const fn id(&self) -> CommandId {
         self.0
     }
 
-    pub const fn new(id: chip_CommandId) -> Self {
+    pub const fn new(id: CommandId) -> Self {
         Self(id)
     }
 }
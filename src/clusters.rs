@@ -0,0 +1,59 @@
+//! Typed IDs of the clusters of the data model CHIP was built with, generated from its `.matter` IDL.
+//!
+//! Each cluster is a module with its `ID`, and with `attributes`, `commands`, `events` and `enums`
+//! submodules, e.g. `clusters::on_off::ID`, `clusters::on_off::attributes::ON_OFF` or
+//! `clusters::on_off::commands::TOGGLE`.
+
+use core::fmt;
+
+use crate::{glue_AttributeId, glue_ClusterId, glue_CommandId};
+
+macro_rules! id {
+    ($(#[$meta:meta])* $name:ident($raw:ty)) => {
+        $(#[$meta])*
+        #[repr(transparent)]
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+        pub struct $name(pub $raw);
+
+        impl $name {
+            /// Get the raw ID
+            pub const fn raw(&self) -> $raw {
+                self.0
+            }
+        }
+
+        impl From<$name> for $raw {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:#06x}", self.0)
+            }
+        }
+    };
+}
+
+id!(
+    /// The ID of a cluster
+    ClusterId(glue_ClusterId)
+);
+
+id!(
+    /// The ID of an attribute, unique within its cluster
+    AttributeId(glue_AttributeId)
+);
+
+id!(
+    /// The ID of a command, unique within its cluster
+    CommandId(glue_CommandId)
+);
+
+id!(
+    /// The ID of an event, unique within its cluster
+    EventId(u32)
+);
+
+include!(env!("GENERATED_CLUSTERS_FILE"));
@@ -1,26 +1,59 @@
+use core::hash::{Hash, Hasher};
+use core::panic::Location;
 use core::{ffi, fmt, slice, str};
 
-use crate::{EmberAfStatus, EmberAfStatus_EMBER_ZCL_STATUS_SUCCESS, CHIP_ERROR};
+use crate::{
+    glue_ChipError, glue_EmberAfStatus, glue_ErrorAsString, GLUE_EMBER_ZCL_STATUS_SUCCESS,
+};
 
-/// A wrapped [`CHIP_ERROR`] to check if an error occurred.
+/// A wrapped [`glue_ChipError`] to check if an error occurred.
 ///
-/// A [`CHIP_ERROR`] is returned from most APIs as a status code. If it is equal
+/// A [`glue_ChipError`] is returned from most APIs as a status code. If it is equal
 /// to [0] it means **no** error occurred.
+///
+/// Two errors are equal if their codes are, so an error can be compared with the
+/// `CHIP_ERROR_*` constants. As these cannot be used as patterns, an error is matched on
+/// with [`ChipError::range`] and the [`ChipErrorRange`] constants of the same names instead:
+///
+/// ```ignore
+/// match error.range() {
+///     ChipErrorRange::INCORRECT_STATE => (),
+///     ChipErrorRange::Posix(errno) => (),
+///     _ => (),
+/// }
+/// ```
+///
+/// Besides the code, an error carries where it originated from: the C++ file and line
+/// in the SDK (when built with `CHIP_CONFIG_ERROR_SOURCE`), the Rust call site which
+/// checked it (see [`chip!`](crate::chip) and [`chip_result!`](crate::chip_result)), and
+/// up to [`ChipError::MAX_CONTEXT`] context messages attached with [`ChipError::context`].
 #[derive(Copy, Clone, Debug)]
-pub struct ChipError(CHIP_ERROR);
+pub struct ChipError {
+    error: glue_ChipError,
+    caller: Option<&'static Location<'static>>,
+    context: [Option<&'static str>; ChipError::MAX_CONTEXT],
+}
 
 impl ChipError {
-    /// Wrap a [CHIP_ERROR]
-    pub const fn from(error: CHIP_ERROR) -> Self {
-        Self(error)
+    /// The maximum number of context messages kept; when more are attached,
+    /// the innermost ones are dropped.
+    pub const MAX_CONTEXT: usize = 3;
+
+    /// Wrap a [glue_ChipError]
+    pub const fn from(error: glue_ChipError) -> Self {
+        Self {
+            error,
+            caller: None,
+            context: [None; Self::MAX_CONTEXT],
+        }
     }
 
-    /// Wrap a [CHIP_ERROR] code
+    /// Wrap a [glue_ChipError] code
     pub const fn from_code(error_code: u32) -> Self {
-        Self(CHIP_ERROR {
-            mError: error_code,
-            mFile: core::ptr::null(),
-            mLine: 0,
+        Self::from(glue_ChipError {
+            code: error_code,
+            file: core::ptr::null(),
+            line: 0,
         })
     }
 
@@ -28,41 +61,96 @@ impl ChipError {
     ///
     /// If `error` is [0] return [`Ok`] of `value` otherwise return [`Err`] of
     /// wrapped `error`.
-    pub fn check_and_return<T>(error: CHIP_ERROR, value: T) -> Result<T, Self> {
-        if error.mError == 0 {
+    ///
+    /// The caller's location is recorded in the error, see [`ChipError::caller`].
+    #[track_caller]
+    pub fn check_and_return<T>(error: glue_ChipError, value: T) -> Result<T, Self> {
+        if error.code == 0 {
             Ok(value)
         } else {
-            Err(Self(error))
+            Err(Self::from(error).with_caller(Location::caller()))
         }
     }
 
     /// Convert `error` into a [`Result`] with `Ok(())` if not error occurred..
     ///
     /// If `error` equals to [0] return [`Ok`], otherwise return [`Err`] with the
-    /// wrapped [`CHIP_ERROR`].
-    pub fn convert(error: CHIP_ERROR) -> Result<(), Self> {
+    /// wrapped [`glue_ChipError`].
+    #[track_caller]
+    pub fn convert(error: glue_ChipError) -> Result<(), Self> {
         Self::check_and_return(error, ())
     }
 
-    /// Panic with a specific error message of the contained [`CHIP_ERROR`].
+    /// Panic with a specific error message of the contained [`glue_ChipError`].
     #[track_caller]
     pub fn panic(&self) {
         panic!("CHIP ERROR: {self}");
     }
 
-    /// Get the wrapped [`CHIP_ERROR`].
-    pub const fn error(self) -> CHIP_ERROR {
-        self.0
+    /// Get the wrapped [`glue_ChipError`].
+    pub const fn error(self) -> glue_ChipError {
+        self.error
+    }
+
+    /// Get the wrapped [`glue_ChipError`] code.
+    pub const fn code(&self) -> u32 {
+        self.error.code
+    }
+
+    /// Get the range of the wrapped [`glue_ChipError`] code, with the code's value within the range.
+    pub const fn range(&self) -> ChipErrorRange {
+        ChipErrorRange::from_code(self.error.code)
+    }
+
+    /// Get the C++ file in the SDK the error originated from.
+    ///
+    /// Only available when the SDK is built with `CHIP_CONFIG_ERROR_SOURCE`.
+    pub fn file(&self) -> Option<&'static str> {
+        if self.error.file.is_null() {
+            None
+        } else {
+            Some(unsafe { c_str(self.error.file) })
+        }
+    }
+
+    /// Get the line in [`ChipError::file`] the error originated from.
+    pub fn line(&self) -> Option<u32> {
+        self.file().map(|_| self.error.line)
+    }
+
+    /// Get the Rust call site which turned the [`glue_ChipError`] into an error.
+    pub const fn caller(&self) -> Option<&'static Location<'static>> {
+        self.caller
+    }
+
+    /// Record `caller` as the Rust call site of the error.
+    pub const fn with_caller(mut self, caller: &'static Location<'static>) -> Self {
+        self.caller = Some(caller);
+        self
+    }
+
+    /// Attach a context message to the error, e.g. `"while initializing server"`.
+    ///
+    /// Messages attached later are outer ones, and are displayed first.
+    pub const fn context(mut self, context: &'static str) -> Self {
+        let mut index = Self::MAX_CONTEXT - 1;
+        while index > 0 {
+            self.context[index] = self.context[index - 1];
+            index -= 1;
+        }
+
+        self.context[0] = Some(context);
+        self
     }
 
-    /// Get the wrapped [`CHIP_ERROR`] code.
-    pub fn code(&self) -> u32 {
-        unsafe { self.0.AsInteger() }
+    /// Get the context messages attached to the error, the outermost one first.
+    pub fn contexts(&self) -> impl Iterator<Item = &'static str> {
+        self.context.into_iter().flatten()
     }
 
-    pub const fn to_raw(result: Result<(), ChipError>) -> CHIP_ERROR {
+    pub const fn to_raw(result: Result<(), ChipError>) -> glue_ChipError {
         let err = match result {
-            Result::Ok(()) => Self::from_code(0),
+            Result::Ok(()) => CHIP_NO_ERROR,
             Result::Err(err) => err,
         };
 
@@ -72,72 +160,264 @@ impl ChipError {
 
 impl fmt::Display for ChipError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        unsafe fn strlen(c_s: *const ffi::c_char) -> usize {
-            let mut len = 0;
-            while *c_s.offset(len) != 0 {
-                len += 1;
-            }
-
-            len as usize
+        for context in self.contexts() {
+            write!(f, "{context}: ")?;
         }
 
-        unsafe {
-            let c_s = self.0.AsString();
-            str::from_utf8_unchecked(slice::from_raw_parts(c_s as *const u8, strlen(c_s))).fmt(f)
+        // Includes the SDK source location, if any
+        unsafe { c_str(glue_ErrorAsString(self.error)) }.fmt(f)?;
+
+        if let Some(caller) = self.caller {
+            write!(f, " (at {caller})")?;
         }
+
+        Ok(())
+    }
+}
+
+/// Attach a context message to the [`ChipError`] of a [`Result`], see [`ChipError::context`].
+pub trait ChipErrorContext {
+    fn context(self, context: &'static str) -> Self;
+}
+
+impl<T> ChipErrorContext for Result<T, ChipError> {
+    fn context(self, context: &'static str) -> Self {
+        self.map_err(|error| error.context(context))
     }
 }
 
+unsafe fn c_str(c_s: *const ffi::c_char) -> &'static str {
+    let mut len = 0;
+    while *c_s.add(len) != 0 {
+        len += 1;
+    }
+
+    str::from_utf8_unchecked(slice::from_raw_parts(c_s as *const u8, len))
+}
+
 #[cfg(feature = "std")]
 impl std::error::Error for ChipError {}
 
 unsafe impl Send for ChipError {}
 unsafe impl Sync for ChipError {}
 
-/// Convert a [`CHIP_ERROR`] into a [`Result<(), ChipError>`](Result).
+impl PartialEq for ChipError {
+    fn eq(&self, other: &Self) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for ChipError {}
+
+impl Hash for ChipError {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state);
+    }
+}
+
+impl From<ChipErrorRange> for ChipError {
+    fn from(range: ChipErrorRange) -> Self {
+        Self::from_code(range.code())
+    }
+}
+
+/// The range of a [`ChipError`] code, as in `CHIP_ERROR`'s `Range` and `SdkPart`,
+/// together with the code's value within the range.
+///
+/// Converts losslessly from and into a [`ChipError`] code. The well-known errors of the
+/// SDK core are available as constants, e.g. [`ChipErrorRange::INCORRECT_STATE`] for
+/// [`CHIP_ERROR_INCORRECT_STATE`], which can be used as patterns.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ChipErrorRange {
+    /// An error of the SDK core; see the `CHIP_ERROR_*` constants
+    Core(u8),
+    /// An error of the Inet layer
+    Inet(u8),
+    /// An error of the device layer
+    Device(u8),
+    /// An ASN.1 error
+    Asn1(u8),
+    /// An error of the BLE layer
+    Ble(u8),
+    /// An Interaction Model global status code
+    ImGlobalStatus(u8),
+    /// An Interaction Model cluster-specific status code
+    ImClusterStatus(u8),
+    /// An application-defined error
+    Application(u8),
+    /// An encapsulated OS error, other than a POSIX `errno` value
+    Os(u32),
+    /// An encapsulated POSIX `errno` value
+    Posix(u32),
+    /// An encapsulated LwIP error
+    LwIp(u32),
+    /// An encapsulated OpenThread error
+    OpenThread(u32),
+    /// A platform-defined error
+    Platform(u32),
+    /// A code which does not belong to any of the ranges above
+    Unknown(u32),
+}
+
+impl ChipErrorRange {
+    const RANGE_START: u32 = 24;
+    const VALUE_MASK: u32 = (1 << Self::RANGE_START) - 1;
+    const SDK_PART_START: u32 = 8;
+    const SDK_PART_END: u32 = 11;
+
+    /// Decode a [`glue_ChipError`] code.
+    pub const fn from_code(code: u32) -> Self {
+        let value = code & Self::VALUE_MASK;
+
+        match code >> Self::RANGE_START {
+            0 if value >> Self::SDK_PART_END == 0 => {
+                let sdk_code = value as u8;
+
+                match value >> Self::SDK_PART_START {
+                    0 => Self::Core(sdk_code),
+                    1 => Self::Inet(sdk_code),
+                    2 => Self::Device(sdk_code),
+                    3 => Self::Asn1(sdk_code),
+                    4 => Self::Ble(sdk_code),
+                    5 => Self::ImGlobalStatus(sdk_code),
+                    6 => Self::ImClusterStatus(sdk_code),
+                    _ => Self::Application(sdk_code),
+                }
+            }
+            1 => Self::Os(value),
+            2 => Self::Posix(value),
+            3 => Self::LwIp(value),
+            4 => Self::OpenThread(value),
+            5 => Self::Platform(value),
+            _ => Self::Unknown(code),
+        }
+    }
+
+    /// Encode into a [`glue_ChipError`] code.
+    pub const fn code(&self) -> u32 {
+        const fn sdk(part: u32, code: u8) -> u32 {
+            (part << ChipErrorRange::SDK_PART_START) | code as u32
+        }
+
+        const fn range(range: u32, value: u32) -> u32 {
+            (range << ChipErrorRange::RANGE_START) | (value & ChipErrorRange::VALUE_MASK)
+        }
+
+        match *self {
+            Self::Core(code) => sdk(0, code),
+            Self::Inet(code) => sdk(1, code),
+            Self::Device(code) => sdk(2, code),
+            Self::Asn1(code) => sdk(3, code),
+            Self::Ble(code) => sdk(4, code),
+            Self::ImGlobalStatus(code) => sdk(5, code),
+            Self::ImClusterStatus(code) => sdk(6, code),
+            Self::Application(code) => sdk(7, code),
+            Self::Os(value) => range(1, value),
+            Self::Posix(value) => range(2, value),
+            Self::LwIp(value) => range(3, value),
+            Self::OpenThread(value) => range(4, value),
+            Self::Platform(value) => range(5, value),
+            Self::Unknown(code) => code,
+        }
+    }
+}
+
+impl From<ChipError> for ChipErrorRange {
+    fn from(error: ChipError) -> Self {
+        error.range()
+    }
+}
+
+macro_rules! chip_errors {
+    ($($name:ident, $range:ident = $code:literal;)*) => {
+        $(pub const $name: ChipError = ChipError::from_code($code);)*
+
+        impl ChipErrorRange {
+            $(
+                #[doc = concat!("The range of [`", stringify!($name), "`], usable as a pattern")]
+                pub const $range: Self = Self::Core($code);
+            )*
+        }
+    };
+}
+
+// Well-known errors of the SDK core, as named in `lib/core/CHIPError.h`
+
+chip_errors! {
+    CHIP_NO_ERROR, NO_ERROR = 0x00;
+    CHIP_ERROR_SENDING_BLOCKED, SENDING_BLOCKED = 0x01;
+    CHIP_ERROR_CONNECTION_ABORTED, CONNECTION_ABORTED = 0x02;
+    CHIP_ERROR_INCORRECT_STATE, INCORRECT_STATE = 0x03;
+    CHIP_ERROR_MESSAGE_TOO_LONG, MESSAGE_TOO_LONG = 0x04;
+    CHIP_ERROR_NO_MEMORY, NO_MEMORY = 0x0b;
+    CHIP_ERROR_MESSAGE_INCOMPLETE, MESSAGE_INCOMPLETE = 0x0d;
+    CHIP_ERROR_KEY_NOT_FOUND, KEY_NOT_FOUND = 0x10;
+    CHIP_ERROR_INTEGRITY_CHECK_FAILED, INTEGRITY_CHECK_FAILED = 0x13;
+    CHIP_ERROR_INVALID_SIGNATURE, INVALID_SIGNATURE = 0x14;
+    CHIP_ERROR_INVALID_MESSAGE_LENGTH, INVALID_MESSAGE_LENGTH = 0x18;
+    CHIP_ERROR_BUFFER_TOO_SMALL, BUFFER_TOO_SMALL = 0x19;
+    CHIP_ERROR_INVALID_STRING_LENGTH, INVALID_STRING_LENGTH = 0x1e;
+    CHIP_ERROR_INVALID_LIST_LENGTH, INVALID_LIST_LENGTH = 0x1f;
+    CHIP_ERROR_END_OF_TLV, END_OF_TLV = 0x21;
+    CHIP_ERROR_TLV_UNDERRUN, TLV_UNDERRUN = 0x22;
+    CHIP_ERROR_INVALID_TLV_ELEMENT, INVALID_TLV_ELEMENT = 0x23;
+    CHIP_ERROR_INVALID_TLV_TAG, INVALID_TLV_TAG = 0x24;
+    CHIP_ERROR_WRONG_TLV_TYPE, WRONG_TLV_TYPE = 0x26;
+    CHIP_ERROR_NOT_IMPLEMENTED, NOT_IMPLEMENTED = 0x2d;
+    CHIP_ERROR_INVALID_ADDRESS, INVALID_ADDRESS = 0x2e;
+    CHIP_ERROR_INVALID_ARGUMENT, INVALID_ARGUMENT = 0x2f;
+    CHIP_ERROR_TIMEOUT, TIMEOUT = 0x32;
+    CHIP_ERROR_PERSISTED_STORAGE_VALUE_NOT_FOUND, PERSISTED_STORAGE_VALUE_NOT_FOUND = 0xa0;
+    CHIP_ERROR_INTERNAL, INTERNAL = 0xac;
+}
+
+/// Convert a [`glue_ChipError`] into a [`Result<(), ChipError>`](Result).
 ///
 /// See [`ChipError::convert`].
 #[macro_export]
 macro_rules! chip {
     ($err:expr) => {{
-        $crate::ChipError::convert($err as $crate::CHIP_ERROR)
+        $crate::ChipError::convert($err as $crate::glue_ChipError)
     }};
 }
 
-/// Convert a [`CHIP_ERROR`] into a [`Result<T, ChipError>`](Result).
+/// Convert a [`glue_ChipError`] into a [`Result<T, ChipError>`](Result).
 ///
 /// See [`ChipError::check_and_return`].
 #[macro_export]
 macro_rules! chip_result {
     ($err:expr, $value:expr) => {{
-        $crate::ChipError::check_and_return($err as $crate::CHIP_ERROR, $value)
+        $crate::ChipError::check_and_return($err as $crate::glue_ChipError, $value)
     }};
 }
 
 /// Panic with an error-specific message if `err` is not [0].
 ///
-/// See [`ChipError::from`] and [`ChipError::panic`].
+/// See [`ChipError::convert`] and [`ChipError::panic`].
 #[macro_export]
 macro_rules! chip_nofail {
     ($err:expr) => {{
-        if let ::core::option::Option::Some(error) =
-            $crate::ChipError::from($err as $crate::CHIP_ERROR)
+        if let ::core::result::Result::Err(error) =
+            $crate::ChipError::convert($err as $crate::glue_ChipError)
         {
             error.panic();
         }
     }};
 }
 
-/// A wrapped [`EmberAfStatus`] to check if an error occurred.
+/// A wrapped [`glue_EmberAfStatus`] to check if an error occurred.
 ///
-/// A [`EmberAfStatus`] is returned from most APIs as a status code. If it is equal
+/// A [`glue_EmberAfStatus`] is returned from most APIs as a status code. If it is equal
 /// to [0] it means **no** error occurred.
+///
+/// Converts into a [`ChipError`] of the [`ChipErrorRange::ImGlobalStatus`] range and back,
+/// except for a success status, which is not an error on either side.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-pub struct EmberAfError(EmberAfStatus);
+pub struct EmberAfError(glue_EmberAfStatus);
 
 impl EmberAfError {
-    /// Wrap a [`EmberAfStatus`]
-    pub const fn from(error: EmberAfStatus) -> Self {
+    /// Wrap a [`glue_EmberAfStatus`]
+    pub const fn from(error: glue_EmberAfStatus) -> Self {
         Self(error)
     }
 
@@ -145,8 +425,8 @@ impl EmberAfError {
     ///
     /// If `error` is [0] return [`Ok`] of `value` otherwise return [`Err`] of
     /// wrapped `error`.
-    pub fn check_and_return<T>(error: EmberAfStatus, value: T) -> Result<T, Self> {
-        if error == EmberAfStatus_EMBER_ZCL_STATUS_SUCCESS {
+    pub fn check_and_return<T>(error: glue_EmberAfStatus, value: T) -> Result<T, Self> {
+        if error == GLUE_EMBER_ZCL_STATUS_SUCCESS {
             Ok(value)
         } else {
             Err(Self(error))
@@ -156,25 +436,25 @@ impl EmberAfError {
     /// Convert `error` into a [`Result`] with `Ok(())` if not error occurred..
     ///
     /// If `error` equals to [0] return [`Ok`], otherwise return [`Err`] with the
-    /// wrapped [`EmberAfStatus`].
-    pub fn convert(error: EmberAfStatus) -> Result<(), Self> {
+    /// wrapped [`glue_EmberAfStatus`].
+    pub fn convert(error: glue_EmberAfStatus) -> Result<(), Self> {
         Self::check_and_return(error, ())
     }
 
-    /// Panic with a specific error message of the contained [`CHIP_ERROR`].
+    /// Panic with a specific error message of the contained [`glue_EmberAfStatus`].
     #[track_caller]
     pub fn panic(&self) {
         panic!("EMBER AF ERROR: {self}");
     }
 
-    /// Get the wrapped [`EmberAfStatus`].
-    pub const fn code(&self) -> EmberAfStatus {
+    /// Get the wrapped [`glue_EmberAfStatus`].
+    pub const fn code(&self) -> glue_EmberAfStatus {
         self.0
     }
 
-    pub const fn to_raw(result: Result<(), EmberAfError>) -> EmberAfStatus {
+    pub const fn to_raw(result: Result<(), EmberAfError>) -> glue_EmberAfStatus {
         match result {
-            Result::Ok(()) => EmberAfStatus_EMBER_ZCL_STATUS_SUCCESS,
+            Result::Ok(()) => GLUE_EMBER_ZCL_STATUS_SUCCESS,
             Result::Err(err) => err.0,
         }
     }
@@ -182,43 +462,322 @@ impl EmberAfError {
 
 impl fmt::Display for EmberAfError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "EMBER AF ERROR: {}", self.0)
+        ImStatus::from(*self).fmt(f)
+    }
+}
+
+impl From<ImStatus> for EmberAfError {
+    fn from(status: ImStatus) -> Self {
+        Self(status.code())
+    }
+}
+
+/// Fails for [`GLUE_EMBER_ZCL_STATUS_SUCCESS`], which is not an error.
+impl TryFrom<EmberAfError> for ChipError {
+    type Error = EmberAfError;
+
+    fn try_from(error: EmberAfError) -> Result<Self, Self::Error> {
+        match ImStatus::from(error) {
+            ImStatus::Success => Err(error),
+            status => Ok(status.into()),
+        }
+    }
+}
+
+/// Fails for [`CHIP_NO_ERROR`], which is not an error, and for errors outside of the
+/// [`ChipErrorRange::ImGlobalStatus`] range.
+impl TryFrom<ChipError> for EmberAfError {
+    type Error = ChipError;
+
+    fn try_from(error: ChipError) -> Result<Self, Self::Error> {
+        match ImStatus::try_from(error) {
+            Ok(ImStatus::Success) | Err(_) => Err(error),
+            Ok(status) => Ok(status.into()),
+        }
     }
 }
 
 #[cfg(feature = "std")]
 impl std::error::Error for EmberAfError {}
 
-/// Convert a [`EmberAfStatus`] into a [`Result<(), EmberAfError>`](Result).
+/// Convert a [`glue_EmberAfStatus`] into a [`Result<(), EmberAfError>`](Result).
 ///
 /// See [`EmberAfError::convert`].
 #[macro_export]
 macro_rules! ember {
     ($err:expr) => {{
-        $crate::EmberAfError::convert($err as $crate::EmberAfStatus)
+        $crate::EmberAfError::convert($err as $crate::glue_EmberAfStatus)
     }};
 }
 
-/// Convert a [`EmberAfStatus`] into a [`Result<T, EmberAfError>`](Result).
+/// Convert a [`glue_EmberAfStatus`] into a [`Result<T, EmberAfError>`](Result).
 ///
 /// See [`EmberAfError::check_and_return`].
 #[macro_export]
 macro_rules! ember_result {
     ($err:expr, $value:expr) => {{
-        $crate::EmberAfError::check_and_return($err as $crate::EmberAfStatus, $value)
+        $crate::EmberAfError::check_and_return($err as $crate::glue_EmberAfStatus, $value)
     }};
 }
 
 /// Panic with an error-specific message if `err` is not [0].
 ///
-/// See [`EmberAfError::from`] and [`EmberAfError::panic`].
+/// See [`EmberAfError::convert`] and [`EmberAfError::panic`].
 #[macro_export]
 macro_rules! ember_nofail {
     ($err:expr) => {{
-        if let ::core::option::Option::Some(error) =
-            $crate::EmberAfError::from($err as $crate::EmberAfStatus)
+        if let ::core::result::Result::Err(error) =
+            $crate::EmberAfError::convert($err as $crate::glue_EmberAfStatus)
         {
             error.panic();
         }
     }};
 }
+
+macro_rules! im_status {
+    ($($(#[$meta:meta])* $variant:ident = $code:literal, $name:literal;)*) => {
+        /// An Interaction Model status code, as in `Protocols::InteractionModel::Status`.
+        ///
+        /// Converts losslessly from and into an [`EmberAfError`] (the values of `EmberAfStatus` are
+        /// the same codes), and from and into a [`ChipError`] of the
+        /// [`ChipErrorRange::ImGlobalStatus`] range ([`ImStatus::Success`] being [`CHIP_NO_ERROR`]).
+        #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+        pub enum ImStatus {
+            $($(#[$meta])* $variant,)*
+            /// A deprecated, reserved or unknown status code
+            Other(u8),
+        }
+
+        impl ImStatus {
+            pub const fn from_code(code: u8) -> Self {
+                match code {
+                    $($code => Self::$variant,)*
+                    other => Self::Other(other),
+                }
+            }
+
+            pub const fn code(&self) -> u8 {
+                match *self {
+                    $(Self::$variant => $code,)*
+                    Self::Other(code) => code,
+                }
+            }
+
+            /// The name of the status in the Matter specification, if it has one
+            pub const fn name(&self) -> Option<&'static str> {
+                match *self {
+                    $(Self::$variant => Some($name),)*
+                    Self::Other(_) => None,
+                }
+            }
+        }
+    };
+}
+
+im_status! {
+    Success = 0x00, "SUCCESS";
+    Failure = 0x01, "FAILURE";
+    InvalidSubscription = 0x7d, "INVALID_SUBSCRIPTION";
+    UnsupportedAccess = 0x7e, "UNSUPPORTED_ACCESS";
+    UnsupportedEndpoint = 0x7f, "UNSUPPORTED_ENDPOINT";
+    InvalidAction = 0x80, "INVALID_ACTION";
+    UnsupportedCommand = 0x81, "UNSUPPORTED_COMMAND";
+    InvalidCommand = 0x85, "INVALID_COMMAND";
+    UnsupportedAttribute = 0x86, "UNSUPPORTED_ATTRIBUTE";
+    ConstraintError = 0x87, "CONSTRAINT_ERROR";
+    UnsupportedWrite = 0x88, "UNSUPPORTED_WRITE";
+    ResourceExhausted = 0x89, "RESOURCE_EXHAUSTED";
+    NotFound = 0x8b, "NOT_FOUND";
+    UnreportableAttribute = 0x8c, "UNREPORTABLE_ATTRIBUTE";
+    InvalidDataType = 0x8d, "INVALID_DATA_TYPE";
+    UnsupportedRead = 0x8f, "UNSUPPORTED_READ";
+    DataVersionMismatch = 0x92, "DATA_VERSION_MISMATCH";
+    Timeout = 0x94, "TIMEOUT";
+    Busy = 0x9c, "BUSY";
+    UnsupportedCluster = 0xc3, "UNSUPPORTED_CLUSTER";
+    NoUpstreamSubscription = 0xc5, "NO_UPSTREAM_SUBSCRIPTION";
+    NeedsTimedInteraction = 0xc6, "NEEDS_TIMED_INTERACTION";
+    UnsupportedEvent = 0xc7, "UNSUPPORTED_EVENT";
+    PathsExhausted = 0xc8, "PATHS_EXHAUSTED";
+    TimedRequestMismatch = 0xc9, "TIMED_REQUEST_MISMATCH";
+    FailsafeRequired = 0xca, "FAILSAFE_REQUIRED";
+    WriteIgnored = 0xf0, "WRITE_IGNORED";
+}
+
+impl fmt::Display for ImStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "{name} ({:#04x})", self.code()),
+            None => write!(f, "IM STATUS {:#04x}", self.code()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ImStatus {}
+
+impl From<EmberAfError> for ImStatus {
+    fn from(error: EmberAfError) -> Self {
+        Self::from_code(error.code())
+    }
+}
+
+impl From<ImStatus> for ChipError {
+    fn from(status: ImStatus) -> Self {
+        match status {
+            ImStatus::Success => CHIP_NO_ERROR,
+            status => ChipErrorRange::ImGlobalStatus(status.code()).into(),
+        }
+    }
+}
+
+impl TryFrom<ChipError> for ImStatus {
+    type Error = ChipError;
+
+    fn try_from(error: ChipError) -> Result<Self, Self::Error> {
+        match error.range() {
+            ChipErrorRange::Core(0) => Ok(Self::Success),
+            ChipErrorRange::ImGlobalStatus(code) if code != 0 => Ok(Self::from_code(code)),
+            _ => Err(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges() {
+        let ranges = [
+            (0x0000_0000, ChipErrorRange::Core(0x00)),
+            (0x0000_00ac, ChipErrorRange::Core(0xac)),
+            (0x0000_0101, ChipErrorRange::Inet(0x01)),
+            (0x0000_0202, ChipErrorRange::Device(0x02)),
+            (0x0000_0303, ChipErrorRange::Asn1(0x03)),
+            (0x0000_0404, ChipErrorRange::Ble(0x04)),
+            (0x0000_0586, ChipErrorRange::ImGlobalStatus(0x86)),
+            (0x0000_0601, ChipErrorRange::ImClusterStatus(0x01)),
+            (0x0000_07ff, ChipErrorRange::Application(0xff)),
+            (0x0000_0800, ChipErrorRange::Unknown(0x0000_0800)),
+            (0x0100_0005, ChipErrorRange::Os(0x05)),
+            (0x0200_000b, ChipErrorRange::Posix(0x0b)),
+            (0x03ff_ffff, ChipErrorRange::LwIp(0x00ff_ffff)),
+            (0x0400_0001, ChipErrorRange::OpenThread(0x01)),
+            (0x0500_0001, ChipErrorRange::Platform(0x01)),
+            (0x0600_0001, ChipErrorRange::Unknown(0x0600_0001)),
+            (0xff00_0000, ChipErrorRange::Unknown(0xff00_0000)),
+        ];
+
+        for (code, range) in ranges {
+            assert_eq!(ChipErrorRange::from_code(code), range, "{code:#010x}");
+            assert_eq!(range.code(), code, "{range:?}");
+            assert_eq!(ChipError::from_code(code).range(), range);
+            assert_eq!(ChipError::code(&range.into()), code);
+        }
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for code in (0..0x1_0000).chain((0..=0xff).map(|range| range << 24 | 0x00ab_cdef)) {
+            assert_eq!(ChipErrorRange::from_code(code).code(), code, "{code:#010x}");
+        }
+    }
+
+    #[test]
+    fn range_constants() {
+        assert_eq!(CHIP_NO_ERROR.range(), ChipErrorRange::NO_ERROR);
+        assert_eq!(
+            ChipError::from_code(ChipErrorRange::INTERNAL.code()),
+            CHIP_ERROR_INTERNAL
+        );
+
+        let describe = |error: ChipError| match error.range() {
+            ChipErrorRange::NO_ERROR => "none",
+            ChipErrorRange::INCORRECT_STATE => "incorrect state",
+            ChipErrorRange::NOT_IMPLEMENTED | ChipErrorRange::INVALID_ARGUMENT => "caller",
+            ChipErrorRange::Core(_) => "core",
+            _ => "other",
+        };
+
+        assert_eq!(describe(CHIP_NO_ERROR), "none");
+        assert_eq!(describe(CHIP_ERROR_INCORRECT_STATE), "incorrect state");
+        assert_eq!(describe(CHIP_ERROR_INVALID_ARGUMENT), "caller");
+        assert_eq!(describe(CHIP_ERROR_TIMEOUT), "core");
+        assert_eq!(describe(ChipErrorRange::Posix(1).into()), "other");
+    }
+
+    #[test]
+    fn im_status() {
+        for code in 0..=u8::MAX {
+            assert_eq!(ImStatus::from_code(code).code(), code);
+        }
+
+        assert_eq!(ImStatus::from_code(0x86), ImStatus::UnsupportedAttribute);
+        assert_eq!(ImStatus::from_code(0x02), ImStatus::Other(0x02));
+
+        assert_eq!(
+            ImStatus::UnsupportedAttribute.to_string(),
+            "UNSUPPORTED_ATTRIBUTE (0x86)"
+        );
+        assert_eq!(ImStatus::Other(0x02).to_string(), "IM STATUS 0x02");
+    }
+
+    #[test]
+    fn im_status_chip_errors() {
+        let error: ChipError = ImStatus::Success.into();
+        assert_eq!(error, CHIP_NO_ERROR);
+        assert_eq!(ImStatus::try_from(CHIP_NO_ERROR), Ok(ImStatus::Success));
+
+        for status in [
+            ImStatus::Failure,
+            ImStatus::UnsupportedAttribute,
+            ImStatus::Other(0x02),
+        ] {
+            let error: ChipError = status.into();
+
+            assert_eq!(error.range(), ChipErrorRange::ImGlobalStatus(status.code()));
+            assert_eq!(ImStatus::try_from(error), Ok(status));
+        }
+
+        // A success status is `CHIP_NO_ERROR`, and not an IM global status error
+        let success: ChipError = ChipErrorRange::ImGlobalStatus(0x00).into();
+        assert_eq!(ImStatus::try_from(success), Err(success));
+
+        assert_eq!(
+            ImStatus::try_from(CHIP_ERROR_INTERNAL),
+            Err(CHIP_ERROR_INTERNAL)
+        );
+        let cluster_status: ChipError = ChipErrorRange::ImClusterStatus(0x01).into();
+        assert_eq!(ImStatus::try_from(cluster_status), Err(cluster_status));
+    }
+
+    #[test]
+    fn ember_errors() {
+        let error: EmberAfError = ImStatus::NotFound.into();
+        assert_eq!(error.code(), 0x8b);
+        assert_eq!(ImStatus::from(error), ImStatus::NotFound);
+        assert_eq!(error.to_string(), "NOT_FOUND (0x8b)");
+
+        let chip_error = ChipError::try_from(EmberAfError::from(0x86)).unwrap();
+        assert_eq!(chip_error.range(), ChipErrorRange::ImGlobalStatus(0x86));
+        assert_eq!(
+            EmberAfError::try_from(chip_error),
+            Ok(EmberAfError::from(0x86))
+        );
+
+        // Success is not an error on either side
+        let success = EmberAfError::from(GLUE_EMBER_ZCL_STATUS_SUCCESS);
+        assert_eq!(ChipError::try_from(success), Err(success));
+        assert_eq!(EmberAfError::try_from(CHIP_NO_ERROR), Err(CHIP_NO_ERROR));
+
+        assert_eq!(
+            EmberAfError::try_from(CHIP_ERROR_INCORRECT_STATE),
+            Err(CHIP_ERROR_INCORRECT_STATE)
+        );
+
+        assert_eq!(EmberAfError::convert(GLUE_EMBER_ZCL_STATUS_SUCCESS), Ok(()));
+        assert_eq!(EmberAfError::to_raw(Err(error)), 0x8b);
+        assert_eq!(EmberAfError::to_raw(Ok(())), GLUE_EMBER_ZCL_STATUS_SUCCESS);
+    }
+}
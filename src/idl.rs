@@ -0,0 +1,1299 @@
+//! A parser for the `.matter` IDL generated by ZAP for a data model.
+//!
+//! The model follows the one of the SDK's own IDL tooling (`scripts/idl`): the clusters with their
+//! enums, bitmaps, structs, attributes, commands and events, and the endpoints with their device
+//! types and the cluster servers instantiated on them.
+//!
+//! This module is also used by the build script of the crate, so it only depends on `core` and `alloc`.
+
+use core::fmt;
+use core::str::FromStr;
+
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// A parsed `.matter` file
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Idl {
+    /// Enums declared outside of any cluster
+    pub enums: Vec<Enum>,
+    /// Bitmaps declared outside of any cluster
+    pub bitmaps: Vec<Bitmap>,
+    /// Structs declared outside of any cluster
+    pub structs: Vec<Struct>,
+    pub clusters: Vec<Cluster>,
+    pub endpoints: Vec<Endpoint>,
+}
+
+impl Idl {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        Parser::new(text)?.parse()
+    }
+
+    pub fn cluster(&self, side: ClusterSide, name: &str) -> Option<&Cluster> {
+        self.clusters
+            .iter()
+            .find(|cluster| cluster.side == side && cluster.name == name)
+    }
+
+    pub fn cluster_by_code(&self, side: ClusterSide, code: u32) -> Option<&Cluster> {
+        self.clusters
+            .iter()
+            .find(|cluster| cluster.side == side && cluster.code == code)
+    }
+
+    pub fn endpoint(&self, number: u16) -> Option<&Endpoint> {
+        self.endpoints
+            .iter()
+            .find(|endpoint| endpoint.number == number)
+    }
+}
+
+impl FromStr for Idl {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        Self::parse(text)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum ClusterSide {
+    Client,
+    Server,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cluster {
+    pub side: ClusterSide,
+    pub name: String,
+    pub code: u32,
+    /// Only present in the IDL of the newer SDK versions
+    pub revision: Option<u32>,
+    pub enums: Vec<Enum>,
+    pub bitmaps: Vec<Bitmap>,
+    pub structs: Vec<Struct>,
+    pub attributes: Vec<Attribute>,
+    pub commands: Vec<Command>,
+    pub events: Vec<Event>,
+}
+
+impl Cluster {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.definition.name == name)
+    }
+
+    pub fn command(&self, name: &str) -> Option<&Command> {
+        self.commands.iter().find(|command| command.name == name)
+    }
+
+    pub fn event(&self, name: &str) -> Option<&Event> {
+        self.events.iter().find(|event| event.name == name)
+    }
+
+    pub fn structure(&self, name: &str) -> Option<&Struct> {
+        self.structs.iter().find(|structure| structure.name == name)
+    }
+
+    /// The struct with the fields of a command; `None` if the command has no fields
+    pub fn request(&self, command: &Command) -> Option<&Struct> {
+        command
+            .input_param
+            .as_deref()
+            .and_then(|name| self.structure(name))
+    }
+
+    /// The struct of the response of a command; `None` if the command responds with a status only
+    pub fn response(&self, command: &Command) -> Option<&Struct> {
+        self.structure(&command.output_param)
+    }
+}
+
+/// An entry of an enum or a bitmap
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstantEntry {
+    pub name: String,
+    pub code: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enum {
+    pub name: String,
+    /// `ENUM8` or `ENUM16`
+    pub base_type: String,
+    pub entries: Vec<ConstantEntry>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bitmap {
+    pub name: String,
+    /// `BITMAP8` ... `BITMAP64`
+    pub base_type: String,
+    pub entries: Vec<ConstantEntry>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum StructTag {
+    /// The fields of a command
+    Request,
+    /// The fields of a command response
+    Response,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct StructQualities {
+    pub fabric_scoped: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Struct {
+    pub name: String,
+    pub tag: Option<StructTag>,
+    /// The command ID of a response struct
+    pub code: Option<u32>,
+    pub qualities: StructQualities,
+    pub fields: Vec<Field>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DataType {
+    /// E.g. `int16u`, `char_string` or the name of an enum, bitmap or struct
+    pub name: String,
+    /// The maximum length of a string type
+    pub max_length: Option<u32>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct FieldQualities {
+    pub optional: bool,
+    pub nullable: bool,
+    pub fabric_sensitive: bool,
+}
+
+/// A field of a struct or an event, or the definition of an attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Field {
+    pub data_type: DataType,
+    pub code: u32,
+    pub name: String,
+    pub is_list: bool,
+    pub qualities: FieldQualities,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum AccessPrivilege {
+    View,
+    Operate,
+    Manage,
+    Administer,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct AttributeQualities {
+    pub readonly: bool,
+    pub nosubscribe: bool,
+    pub timed_write: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attribute {
+    pub definition: Field,
+    pub qualities: AttributeQualities,
+    pub read_access: AccessPrivilege,
+    pub write_access: AccessPrivilege,
+}
+
+impl Attribute {
+    pub fn is_writable(&self) -> bool {
+        !self.qualities.readonly
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct CommandQualities {
+    pub timed: bool,
+    pub fabric_scoped: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Command {
+    pub name: String,
+    pub code: u32,
+    /// The name of the request struct, if the command has fields
+    pub input_param: Option<String>,
+    /// The name of the response struct, or `DefaultSuccess`
+    pub output_param: String,
+    pub qualities: CommandQualities,
+    pub invoke_access: AccessPrivilege,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum EventPriority {
+    Debug,
+    Info,
+    Critical,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EventQualities {
+    pub fabric_sensitive: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub priority: EventPriority,
+    pub name: String,
+    pub code: u32,
+    pub fields: Vec<Field>,
+    pub qualities: EventQualities,
+    pub read_access: AccessPrivilege,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    pub number: u16,
+    pub device_types: Vec<DeviceType>,
+    /// The clusters the endpoint has client bindings for
+    pub client_bindings: Vec<String>,
+    pub server_clusters: Vec<ServerClusterInstantiation>,
+}
+
+impl Endpoint {
+    pub fn server_cluster(&self, name: &str) -> Option<&ServerClusterInstantiation> {
+        self.server_clusters
+            .iter()
+            .find(|cluster| cluster.name == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceType {
+    pub name: String,
+    pub code: u32,
+    pub version: u32,
+}
+
+/// A cluster server enabled on an endpoint
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ServerClusterInstantiation {
+    pub name: String,
+    pub attributes: Vec<AttributeInstantiation>,
+    /// Only present in the IDL of the newer SDK versions
+    pub events_emitted: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum AttributeStorage {
+    Ram,
+    Persist,
+    Callback,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeInstantiation {
+    pub name: String,
+    pub storage: AttributeStorage,
+    /// The default value as written in the IDL, i.e. a number, or a quoted string
+    pub default: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum TokenKind {
+    Ident,
+    Number,
+    Str,
+    Punct,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+    line: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let start = pos;
+        let ch = bytes[pos];
+
+        let kind = if ch == b'\n' {
+            line += 1;
+            pos += 1;
+            None
+        } else if ch.is_ascii_whitespace() {
+            pos += 1;
+            None
+        } else if text[pos..].starts_with("//") {
+            pos = text[pos..].find('\n').map_or(bytes.len(), |end| pos + end);
+            None
+        } else if text[pos..].starts_with("/*") {
+            let end = text[pos + 2..]
+                .find("*/")
+                .map(|end| pos + 2 + end + 2)
+                .ok_or_else(|| ParseError {
+                    line,
+                    message: "Unterminated comment".to_owned(),
+                })?;
+
+            line += text[pos..end].matches('\n').count();
+            pos = end;
+            None
+        } else if ch.is_ascii_alphabetic() || ch == b'_' {
+            while pos < bytes.len() && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_') {
+                pos += 1;
+            }
+
+            Some(TokenKind::Ident)
+        } else if ch.is_ascii_digit()
+            || (ch == b'-' && matches!(bytes.get(pos + 1), Some(next) if next.is_ascii_digit()))
+        {
+            pos += 1;
+
+            while pos < bytes.len() && bytes[pos].is_ascii_alphanumeric() {
+                pos += 1;
+            }
+
+            Some(TokenKind::Number)
+        } else if ch == b'"' {
+            pos = text[pos + 1..]
+                .find('"')
+                .map(|end| pos + 1 + end + 1)
+                .ok_or_else(|| ParseError {
+                    line,
+                    message: "Unterminated string".to_owned(),
+                })?;
+
+            Some(TokenKind::Str)
+        } else if b"{}()[]<>;:,=".contains(&ch) {
+            pos += 1;
+
+            Some(TokenKind::Punct)
+        } else {
+            return Err(ParseError {
+                line,
+                message: format!("Unexpected character `{}`", ch as char),
+            });
+        };
+
+        if let Some(kind) = kind {
+            tokens.push(Token {
+                kind,
+                text: &text[start..pos],
+                line,
+            });
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(text: &'a str) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(text)?,
+            pos: 0,
+        })
+    }
+
+    fn parse(mut self) -> Result<Idl, ParseError> {
+        let mut idl = Idl::default();
+
+        while self.peek().is_some() {
+            let qualifiers = self.qualifiers();
+
+            match self.ident()? {
+                "endpoint" => {
+                    self.no_qualifiers(&qualifiers, "endpoint")?;
+                    idl.endpoints.push(self.endpoint()?);
+                }
+                "cluster" => {
+                    let side = match qualifiers.as_slice() {
+                        ["client"] => ClusterSide::Client,
+                        ["server"] => ClusterSide::Server,
+                        _ => {
+                            return Err(self.error("Expected `client cluster` or `server cluster`"))
+                        }
+                    };
+
+                    idl.clusters.push(self.cluster(side)?);
+                }
+                "enum" => {
+                    self.no_qualifiers(&qualifiers, "enum")?;
+                    idl.enums.push(self.enumeration()?);
+                }
+                "bitmap" => {
+                    self.no_qualifiers(&qualifiers, "bitmap")?;
+                    idl.bitmaps.push(self.bitmap()?);
+                }
+                "struct" => idl.structs.push(self.structure(&qualifiers)?),
+                other => return Err(self.error(&format!("Unexpected `{other}`"))),
+            }
+        }
+
+        Ok(idl)
+    }
+
+    fn cluster(&mut self, side: ClusterSide) -> Result<Cluster, ParseError> {
+        let name = self.ident()?.to_owned();
+        self.punct('=')?;
+        let code = self.number()?;
+        self.punct('{')?;
+
+        let mut cluster = Cluster {
+            side,
+            name,
+            code,
+            revision: None,
+            enums: Vec::new(),
+            bitmaps: Vec::new(),
+            structs: Vec::new(),
+            attributes: Vec::new(),
+            commands: Vec::new(),
+            events: Vec::new(),
+        };
+
+        while !self.eat_punct('}') {
+            let qualifiers = self.qualifiers();
+
+            match self.ident()? {
+                "revision" => {
+                    self.no_qualifiers(&qualifiers, "revision")?;
+                    cluster.revision = Some(self.number()?);
+                    self.punct(';')?;
+                }
+                "enum" => {
+                    self.no_qualifiers(&qualifiers, "enum")?;
+                    cluster.enums.push(self.enumeration()?);
+                }
+                "bitmap" => {
+                    self.no_qualifiers(&qualifiers, "bitmap")?;
+                    cluster.bitmaps.push(self.bitmap()?);
+                }
+                "struct" => cluster.structs.push(self.structure(&qualifiers)?),
+                "attribute" => cluster.attributes.push(self.attribute(&qualifiers)?),
+                "command" => cluster.commands.push(self.command(&qualifiers)?),
+                "event" => cluster.events.push(self.event(&qualifiers)?),
+                other => return Err(self.error(&format!("Unexpected `{other}` in a cluster"))),
+            }
+        }
+
+        Ok(cluster)
+    }
+
+    fn enumeration(&mut self) -> Result<Enum, ParseError> {
+        let (name, base_type, entries) = self.constants()?;
+
+        Ok(Enum {
+            name,
+            base_type,
+            entries,
+        })
+    }
+
+    fn bitmap(&mut self) -> Result<Bitmap, ParseError> {
+        let (name, base_type, entries) = self.constants()?;
+
+        Ok(Bitmap {
+            name,
+            base_type,
+            entries,
+        })
+    }
+
+    /// `<name> : <base type> { <entry> = <code>; ... }`
+    fn constants(&mut self) -> Result<(String, String, Vec<ConstantEntry>), ParseError> {
+        let name = self.ident()?.to_owned();
+        self.punct(':')?;
+        let base_type = self.ident()?.to_owned();
+        self.punct('{')?;
+
+        let mut entries = Vec::new();
+
+        while !self.eat_punct('}') {
+            let name = self.ident()?.to_owned();
+            self.punct('=')?;
+            let code = self.number()?;
+            self.punct(';')?;
+
+            entries.push(ConstantEntry { name, code });
+        }
+
+        Ok((name, base_type, entries))
+    }
+
+    /// `[fabric_scoped] struct <name> { ... }`, `request struct <name> { ... }`
+    /// or `response struct <name> = <code> { ... }`
+    fn structure(&mut self, qualifiers: &[&str]) -> Result<Struct, ParseError> {
+        let mut tag = None;
+        let mut struct_qualities = StructQualities::default();
+
+        for qualifier in qualifiers {
+            match *qualifier {
+                "request" => tag = Some(StructTag::Request),
+                "response" => tag = Some(StructTag::Response),
+                "fabric_scoped" => struct_qualities.fabric_scoped = true,
+                other => return Err(self.error(&format!("Unexpected `{other}` for a struct"))),
+            }
+        }
+
+        let name = self.ident()?.to_owned();
+
+        let code = if tag == Some(StructTag::Response) {
+            self.punct('=')?;
+            Some(self.number()?)
+        } else {
+            None
+        };
+
+        Ok(Struct {
+            name,
+            tag,
+            code,
+            qualities: struct_qualities,
+            fields: self.fields()?,
+        })
+    }
+
+    /// `{ <field>; ... }`
+    fn fields(&mut self) -> Result<Vec<Field>, ParseError> {
+        self.punct('{')?;
+
+        let mut fields = Vec::new();
+
+        while !self.eat_punct('}') {
+            fields.push(self.field()?);
+            self.punct(';')?;
+        }
+
+        Ok(fields)
+    }
+
+    /// `[optional] [nullable] [fabric_sensitive] <type>[<<max length>>] <name>[[]] = <code>`
+    fn field(&mut self) -> Result<Field, ParseError> {
+        let mut qualities = FieldQualities::default();
+
+        loop {
+            if self.eat_ident("optional") {
+                qualities.optional = true;
+            } else if self.eat_ident("nullable") {
+                qualities.nullable = true;
+            } else if self.eat_ident("fabric_sensitive") {
+                qualities.fabric_sensitive = true;
+            } else {
+                break;
+            }
+        }
+
+        let type_name = self.ident()?.to_owned();
+
+        let max_length = if self.eat_punct('<') {
+            let max_length = self.number()?;
+            self.punct('>')?;
+
+            Some(max_length)
+        } else {
+            None
+        };
+
+        let name = self.ident()?.to_owned();
+
+        let is_list = self.eat_punct('[');
+        if is_list {
+            self.punct(']')?;
+        }
+
+        self.punct('=')?;
+        let code = self.number()?;
+
+        Ok(Field {
+            data_type: DataType {
+                name: type_name,
+                max_length,
+            },
+            code,
+            name,
+            is_list,
+            qualities,
+        })
+    }
+
+    /// `[readonly] [nosubscribe] [timedwrite] attribute [access(...)] <field>;`
+    fn attribute(&mut self, qualifiers: &[&str]) -> Result<Attribute, ParseError> {
+        let mut qualities = AttributeQualities::default();
+
+        for qualifier in qualifiers {
+            match *qualifier {
+                "readonly" => qualities.readonly = true,
+                "nosubscribe" => qualities.nosubscribe = true,
+                "timedwrite" => qualities.timed_write = true,
+                other => return Err(self.error(&format!("Unexpected `{other}` for an attribute"))),
+            }
+        }
+
+        let mut read_access = AccessPrivilege::View;
+        let mut write_access = AccessPrivilege::Operate;
+
+        for (kind, privilege) in self.access()? {
+            match kind {
+                "read" => read_access = privilege,
+                "write" => write_access = privilege,
+                other => {
+                    return Err(self.error(&format!("Unexpected `{other}` access for an attribute")))
+                }
+            }
+        }
+
+        let definition = self.field()?;
+        self.punct(';')?;
+
+        Ok(Attribute {
+            definition,
+            qualities,
+            read_access,
+            write_access,
+        })
+    }
+
+    /// `[timed] [fabric] command [access(...)] <name>([<request>]): <response> = <code>;`
+    fn command(&mut self, qualifiers: &[&str]) -> Result<Command, ParseError> {
+        let mut qualities = CommandQualities::default();
+
+        for qualifier in qualifiers {
+            match *qualifier {
+                "timed" => qualities.timed = true,
+                "fabric" => qualities.fabric_scoped = true,
+                other => return Err(self.error(&format!("Unexpected `{other}` for a command"))),
+            }
+        }
+
+        let mut invoke_access = AccessPrivilege::Operate;
+
+        for (kind, privilege) in self.access()? {
+            match kind {
+                "invoke" => invoke_access = privilege,
+                other => {
+                    return Err(self.error(&format!("Unexpected `{other}` access for a command")))
+                }
+            }
+        }
+
+        let name = self.ident()?.to_owned();
+
+        self.punct('(')?;
+        let input_param = if self.eat_punct(')') {
+            None
+        } else {
+            let input_param = self.ident()?.to_owned();
+            self.punct(')')?;
+
+            Some(input_param)
+        };
+
+        self.punct(':')?;
+        let output_param = self.ident()?.to_owned();
+        self.punct('=')?;
+        let code = self.number()?;
+        self.punct(';')?;
+
+        Ok(Command {
+            name,
+            code,
+            input_param,
+            output_param,
+            qualities,
+            invoke_access,
+        })
+    }
+
+    /// `[fabric_sensitive] <priority> event [access(...)] <name> = <code> { <field>; ... }`
+    fn event(&mut self, qualifiers: &[&str]) -> Result<Event, ParseError> {
+        let mut qualities = EventQualities::default();
+        let mut priority = None;
+
+        for qualifier in qualifiers {
+            match *qualifier {
+                "fabric_sensitive" => qualities.fabric_sensitive = true,
+                "debug" => priority = Some(EventPriority::Debug),
+                "info" => priority = Some(EventPriority::Info),
+                "critical" => priority = Some(EventPriority::Critical),
+                other => return Err(self.error(&format!("Unexpected `{other}` for an event"))),
+            }
+        }
+
+        let priority = priority.ok_or_else(|| self.error("Missing event priority"))?;
+
+        let mut read_access = AccessPrivilege::View;
+
+        for (kind, privilege) in self.access()? {
+            match kind {
+                "read" => read_access = privilege,
+                other => {
+                    return Err(self.error(&format!("Unexpected `{other}` access for an event")))
+                }
+            }
+        }
+
+        let name = self.ident()?.to_owned();
+        self.punct('=')?;
+        let code = self.number()?;
+
+        Ok(Event {
+            priority,
+            name,
+            code,
+            fields: self.fields()?,
+            qualities,
+            read_access,
+        })
+    }
+
+    /// `access(<kind>: <privilege>, ...)`, if present
+    fn access(&mut self) -> Result<Vec<(&'a str, AccessPrivilege)>, ParseError> {
+        let mut access = Vec::new();
+
+        if self.eat_ident("access") {
+            self.punct('(')?;
+
+            loop {
+                let kind = self.ident()?;
+                self.punct(':')?;
+
+                let privilege = match self.ident()? {
+                    "view" => AccessPrivilege::View,
+                    "operate" => AccessPrivilege::Operate,
+                    "manage" => AccessPrivilege::Manage,
+                    "administer" => AccessPrivilege::Administer,
+                    other => return Err(self.error(&format!("Unknown privilege `{other}`"))),
+                };
+
+                access.push((kind, privilege));
+
+                if !self.eat_punct(',') {
+                    break;
+                }
+            }
+
+            self.punct(')')?;
+        }
+
+        Ok(access)
+    }
+
+    fn endpoint(&mut self) -> Result<Endpoint, ParseError> {
+        let mut endpoint = Endpoint {
+            number: self.number()?,
+            device_types: Vec::new(),
+            client_bindings: Vec::new(),
+            server_clusters: Vec::new(),
+        };
+
+        self.punct('{')?;
+
+        while !self.eat_punct('}') {
+            match self.ident()? {
+                // `device type <name> = <code>[, version <version>];`
+                "device" => {
+                    self.keyword("type")?;
+
+                    let name = self.ident()?.to_owned();
+                    self.punct('=')?;
+                    let code = self.number()?;
+
+                    let version = if self.eat_punct(',') {
+                        self.keyword("version")?;
+                        self.number()?
+                    } else {
+                        1
+                    };
+
+                    self.punct(';')?;
+
+                    endpoint.device_types.push(DeviceType {
+                        name,
+                        code,
+                        version,
+                    });
+                }
+                // `binding cluster <name>;`
+                "binding" => {
+                    self.keyword("cluster")?;
+                    endpoint.client_bindings.push(self.ident()?.to_owned());
+                    self.punct(';')?;
+                }
+                // `server cluster <name> { ... }`
+                "server" => {
+                    self.keyword("cluster")?;
+                    endpoint.server_clusters.push(self.server_cluster()?);
+                }
+                other => return Err(self.error(&format!("Unexpected `{other}` in an endpoint"))),
+            }
+        }
+
+        Ok(endpoint)
+    }
+
+    fn server_cluster(&mut self) -> Result<ServerClusterInstantiation, ParseError> {
+        let mut cluster = ServerClusterInstantiation {
+            name: self.ident()?.to_owned(),
+            attributes: Vec::new(),
+            events_emitted: Vec::new(),
+        };
+
+        self.punct('{')?;
+
+        while !self.eat_punct('}') {
+            let storage = match self.ident()? {
+                "ram" => AttributeStorage::Ram,
+                "persist" => AttributeStorage::Persist,
+                "callback" => AttributeStorage::Callback,
+                // `emits event <name>;`
+                "emits" => {
+                    self.keyword("event")?;
+                    cluster.events_emitted.push(self.ident()?.to_owned());
+                    self.punct(';')?;
+
+                    continue;
+                }
+                other => {
+                    return Err(self.error(&format!("Unexpected `{other}` in a cluster server")))
+                }
+            };
+
+            // `<storage> attribute <name> [default = <value>];`
+            self.keyword("attribute")?;
+            let name = self.ident()?.to_owned();
+
+            let default = if self.eat_ident("default") {
+                self.punct('=')?;
+
+                let token = self.next()?;
+                if !matches!(
+                    token.kind,
+                    TokenKind::Number | TokenKind::Str | TokenKind::Ident
+                ) {
+                    return Err(self.error(&format!("Unexpected `{}`", token.text)));
+                }
+
+                Some(token.text.to_owned())
+            } else {
+                None
+            };
+
+            self.punct(';')?;
+
+            cluster.attributes.push(AttributeInstantiation {
+                name,
+                storage,
+                default,
+            });
+        }
+
+        Ok(cluster)
+    }
+
+    /// The identifiers preceding the keyword of a declaration, e.g. `readonly` in `readonly attribute ...`
+    fn qualifiers(&mut self) -> Vec<&'a str> {
+        const KEYWORDS: &[&str] = &[
+            "cluster",
+            "endpoint",
+            "enum",
+            "bitmap",
+            "struct",
+            "attribute",
+            "command",
+            "event",
+            "revision",
+        ];
+
+        let mut qualifiers = Vec::new();
+
+        while let Some(token) = self.peek() {
+            if token.kind != TokenKind::Ident || KEYWORDS.contains(&token.text) {
+                break;
+            }
+
+            qualifiers.push(token.text);
+            self.pos += 1;
+        }
+
+        qualifiers
+    }
+
+    fn no_qualifiers(&self, qualifiers: &[&str], what: &str) -> Result<(), ParseError> {
+        match qualifiers.first() {
+            Some(qualifier) => Err(self.error(&format!("Unexpected `{qualifier}` for {what}"))),
+            None => Ok(()),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token<'a>, ParseError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| self.error("Unexpected end of file"))?;
+
+        self.pos += 1;
+
+        Ok(token)
+    }
+
+    fn ident(&mut self) -> Result<&'a str, ParseError> {
+        let token = self.next()?;
+
+        if token.kind == TokenKind::Ident {
+            Ok(token.text)
+        } else {
+            Err(self.error(&format!("Expected an identifier, got `{}`", token.text)))
+        }
+    }
+
+    fn number<T: TryFrom<u64>>(&mut self) -> Result<T, ParseError> {
+        let token = self.next()?;
+
+        let value = if token.kind != TokenKind::Number {
+            None
+        } else if let Some(hex) = token
+            .text
+            .strip_prefix("0x")
+            .or_else(|| token.text.strip_prefix("0X"))
+        {
+            u64::from_str_radix(hex, 16).ok()
+        } else {
+            token.text.parse().ok()
+        };
+
+        value
+            .and_then(|value| T::try_from(value).ok())
+            .ok_or_else(|| self.error(&format!("Expected a number, got `{}`", token.text)))
+    }
+
+    fn keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_ident(keyword) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{keyword}`")))
+        }
+    }
+
+    fn punct(&mut self, punct: char) -> Result<(), ParseError> {
+        if self.eat_punct(punct) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected `{punct}`")))
+        }
+    }
+
+    fn eat_ident(&mut self, ident: &str) -> bool {
+        self.eat(TokenKind::Ident, ident)
+    }
+
+    fn eat_punct(&mut self, punct: char) -> bool {
+        let mut buf = [0; 4];
+
+        self.eat(TokenKind::Punct, punct.encode_utf8(&mut buf))
+    }
+
+    fn eat(&mut self, kind: TokenKind, text: &str) -> bool {
+        let matches =
+            matches!(self.peek(), Some(token) if token.kind == kind && token.text == text);
+
+        if matches {
+            self.pos += 1;
+        }
+
+        matches
+    }
+
+    /// An error at the last consumed token
+    fn error(&self, message: &str) -> ParseError {
+        let line = self
+            .tokens
+            .get(self.pos.saturating_sub(1))
+            .or_else(|| self.tokens.last())
+            .map_or(1, |token| token.line);
+
+        ParseError {
+            line,
+            message: message.to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::*;
+
+    /// Modelled on the IDL of the `bridge-app` data model of the SDK, which is not vendored: a root
+    /// node, an aggregator and a template endpoint with the `BridgedDeviceBasic` cluster
+    const BRIDGE_APP: &str = r#"
+        // This IDL was generated automatically by ZAP.
+        server cluster BridgedDeviceBasic = 57 {
+          readonly attribute char_string<32> nodeLabel = 5;
+          readonly attribute boolean reachable = 17;
+          readonly attribute command_id generatedCommandList[] = 65528;
+          readonly attribute int16u clusterRevision = 65533;
+        }
+
+        endpoint 0 {
+          device type rootdevice = 22, version 1;
+        }
+        endpoint 1 {
+          device type bridge = 14, version 1;
+          binding cluster Binding;
+        }
+        endpoint 2 {
+          device type dimmablelight = 0x0101, version 1;
+
+          server cluster BridgedDeviceBasic {
+            callback attribute nodeLabel;
+            callback attribute reachable;
+            callback attribute generatedCommandList;
+            ram      attribute clusterRevision default = 0x0001;
+          }
+        }
+    "#;
+
+    fn parse_error(text: &str) -> ParseError {
+        Idl::parse(text).expect_err("Expected a parse error")
+    }
+
+    #[test]
+    fn ember() {
+        let idl = Idl::parse(include_str!("../lib/ember/ember.matter")).unwrap();
+
+        let on_off = idl.cluster_by_code(ClusterSide::Server, 6).unwrap();
+        assert_eq!(on_off.name, "OnOff");
+        assert!(on_off.attribute("onOff").is_some());
+
+        assert!(idl.cluster(ClusterSide::Client, "AccessControl").is_some());
+        assert!(idl.cluster(ClusterSide::Server, "Actions").is_some());
+
+        let endpoints = idl
+            .endpoints
+            .iter()
+            .map(|endpoint| endpoint.number)
+            .collect::<Vec<_>>();
+        assert_eq!(endpoints, [0, 1, 2]);
+
+        let light = idl.endpoint(2).unwrap();
+        assert_eq!(light.device_types[0].name, "dimmablelight");
+        assert_eq!(light.device_types[0].code, 257);
+        assert!(light.server_cluster("LevelControl").is_some());
+
+        let bridge = idl.endpoint(1).unwrap();
+        assert_eq!(bridge.client_bindings, ["Binding"]);
+
+        let actions = bridge.server_cluster("Actions").unwrap();
+        let setup_url = actions
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name == "setupURL")
+            .unwrap();
+        assert_eq!(setup_url.storage, AttributeStorage::Ram);
+        assert_eq!(
+            setup_url.default.as_deref(),
+            Some("\"https://example.com\"")
+        );
+    }
+
+    #[test]
+    fn bridge_app() {
+        let idl = Idl::parse(BRIDGE_APP).unwrap();
+
+        let bridged = idl
+            .cluster(ClusterSide::Server, "BridgedDeviceBasic")
+            .unwrap();
+        assert_eq!(bridged.code, 0x0039);
+
+        let node_label = bridged.attribute("nodeLabel").unwrap();
+        assert!(node_label.qualities.readonly);
+        assert!(!node_label.is_writable());
+        assert_eq!(node_label.definition.data_type.name, "char_string");
+        assert_eq!(node_label.definition.data_type.max_length, Some(32));
+
+        assert!(
+            bridged
+                .attribute("generatedCommandList")
+                .unwrap()
+                .definition
+                .is_list
+        );
+
+        assert_eq!(idl.endpoint(1).unwrap().device_types[0].code, 14);
+
+        let template = idl.endpoint(2).unwrap();
+        assert_eq!(template.device_types[0].code, 0x0101);
+        assert_eq!(template.device_types[0].version, 1);
+
+        let instantiation = template.server_cluster("BridgedDeviceBasic").unwrap();
+        assert_eq!(instantiation.attributes.len(), 4);
+        assert_eq!(
+            instantiation.attributes[1].storage,
+            AttributeStorage::Callback
+        );
+        assert_eq!(
+            instantiation.attributes[3].default.as_deref(),
+            Some("0x0001")
+        );
+    }
+
+    #[test]
+    fn numbers() {
+        let idl = Idl::parse(
+            "enum Mode : ENUM8 { kDecimal = 16; kHex = 0x10; kUpperHex = 0X1F; }
+             server cluster OnOff = 0x0006 { revision 4; }",
+        )
+        .unwrap();
+
+        let codes = idl.enums[0]
+            .entries
+            .iter()
+            .map(|entry| entry.code)
+            .collect::<Vec<_>>();
+        assert_eq!(codes, [16, 16, 31]);
+
+        assert_eq!(idl.clusters[0].code, 6);
+        assert_eq!(idl.clusters[0].revision, Some(4));
+
+        assert_eq!(
+            parse_error("server cluster OnOff = 0x10000000000000000 {}").line,
+            1
+        );
+        assert_eq!(parse_error("endpoint 65536 {}").line, 1);
+    }
+
+    #[test]
+    fn qualifiers() {
+        let idl = Idl::parse(
+            "server cluster GeneralCommissioning = 48 {
+               fabric_scoped struct Entry { fabric_sensitive octet_string<128> data = 1; }
+               request struct ArmFailSafeRequest { INT16U expiryLengthSeconds = 0; }
+               response struct ArmFailSafeResponse = 1 { optional nullable CHAR_STRING debugText = 1; }
+               fabric_sensitive info event access(read: administer) Changed = 0 { fabric_idx fabricIndex = 254; }
+               critical event StartUp = 1 {}
+               readonly nosubscribe attribute int16u counter = 0;
+               timedwrite attribute access(read: manage, write: administer) int64u breadcrumb = 1;
+               command access(invoke: administer) ArmFailSafe(ArmFailSafeRequest): ArmFailSafeResponse = 0;
+               timed fabric command Complete(): DefaultSuccess = 4;
+             }",
+        )
+        .unwrap();
+
+        let cluster = &idl.clusters[0];
+
+        let entry = cluster.structure("Entry").unwrap();
+        assert!(entry.qualities.fabric_scoped);
+        assert_eq!(entry.tag, None);
+        assert!(entry.fields[0].qualities.fabric_sensitive);
+
+        let request = cluster.structure("ArmFailSafeRequest").unwrap();
+        assert_eq!(request.tag, Some(StructTag::Request));
+        assert_eq!(request.code, None);
+
+        let response = cluster.structure("ArmFailSafeResponse").unwrap();
+        assert_eq!(response.tag, Some(StructTag::Response));
+        assert_eq!(response.code, Some(1));
+        assert!(response.fields[0].qualities.optional);
+        assert!(response.fields[0].qualities.nullable);
+
+        let changed = cluster.event("Changed").unwrap();
+        assert!(changed.qualities.fabric_sensitive);
+        assert_eq!(changed.priority, EventPriority::Info);
+        assert_eq!(changed.read_access, AccessPrivilege::Administer);
+
+        let start_up = cluster.event("StartUp").unwrap();
+        assert!(!start_up.qualities.fabric_sensitive);
+        assert_eq!(start_up.priority, EventPriority::Critical);
+        assert_eq!(start_up.read_access, AccessPrivilege::View);
+
+        let counter = cluster.attribute("counter").unwrap();
+        assert!(counter.qualities.readonly);
+        assert!(counter.qualities.nosubscribe);
+
+        let breadcrumb = cluster.attribute("breadcrumb").unwrap();
+        assert!(breadcrumb.qualities.timed_write);
+        assert_eq!(breadcrumb.read_access, AccessPrivilege::Manage);
+        assert_eq!(breadcrumb.write_access, AccessPrivilege::Administer);
+
+        let arm_fail_safe = cluster.command("ArmFailSafe").unwrap();
+        assert_eq!(arm_fail_safe.invoke_access, AccessPrivilege::Administer);
+        assert_eq!(
+            cluster
+                .request(arm_fail_safe)
+                .map(|request| request.name.as_str()),
+            Some("ArmFailSafeRequest")
+        );
+        assert_eq!(
+            cluster
+                .response(arm_fail_safe)
+                .map(|response| response.name.as_str()),
+            Some("ArmFailSafeResponse")
+        );
+
+        let complete = cluster.command("Complete").unwrap();
+        assert!(complete.qualities.timed);
+        assert!(complete.qualities.fabric_scoped);
+        assert_eq!(complete.input_param, None);
+        assert_eq!(complete.invoke_access, AccessPrivilege::Operate);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_error("server cluster OnOff = 6 {\n  attribute int16u = 0;\n}"),
+            ParseError {
+                line: 2,
+                message: "Expected an identifier, got `=`".to_owned(),
+            }
+        );
+
+        assert_eq!(parse_error("// Comment\n\nendpoint 0 {\n  $\n}").line, 4);
+        assert_eq!(parse_error("/* Multi\nline */ endpoint 0 {\n").line, 2);
+        assert_eq!(
+            parse_error("\n\nclient server cluster OnOff = 6 {}").line,
+            3
+        );
+        assert_eq!(parse_error("endpoint 0 {\n  device type \"root\n}").line, 2);
+
+        let error = parse_error("\nfabric endpoint 0 {}");
+        assert_eq!(error.line, 2);
+        assert!(error.to_string().starts_with("line 2: "));
+    }
+}
@@ -1,28 +0,0 @@
-#include "config.h"
-
-#include "lib/core/CHIPError.h"
-#include "lib/core/DataModelTypes.h"
-#include "credentials/examples/DeviceAttestationCredsExample.h"
-#include "platform/ConfigurationManager.h"
-#include "platform/PlatformManager.h"
-#include "app/reporting/reporting.h"
-#include "app/InteractionModelEngine.h"
-#include "app/server/Dnssd.h"
-#include "app/server/Server.h"
-#include "app/server/OnboardingCodesUtil.h"
-#include "app/util/af.h"
-#include "app/util/attribute-storage.h"
-
-#include "app-common/zap-generated/enums.h"
-#include "app-common/zap-generated/af-structs.h"
-#include "app-common/zap-generated/att-storage.h"
-#include "app-common/zap-generated/attribute-id.h"
-//#include "app-common/zap-generated/attribute-size.h"
-#include "app-common/zap-generated/attribute-type.h"
-#include "app-common/zap-generated/callback.h"
-#include "app-common/zap-generated/cluster-enums.h"
-#include "app-common/zap-generated/cluster-id.h"
-#include "app-common/zap-generated/cluster-objects.h"
-#include "app-common/zap-generated/command-id.h"
-
-#include "glue.h"
@@ -1,19 +1,25 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
 pub use bindings::*;
 pub use error::*;
+pub use zcl::*;
 
 pub mod cb;
 pub mod chip;
+pub mod clusters;
 mod error;
+#[cfg(feature = "idl")]
+pub mod idl;
+mod zcl;
 
 #[allow(clippy::all)]
 #[allow(non_upper_case_globals)]
 #[allow(non_camel_case_types)]
 #[allow(non_snake_case)]
 #[allow(rustdoc::all)]
-#[allow(improper_ctypes)] // TODO: For now, as 5.0 spits out tons of these
-#[allow(clashing_extern_declarations)] // TODO
 mod bindings {
     include!(env!("GENERATED_BINDINGS_FILE"));
 }
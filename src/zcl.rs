@@ -0,0 +1,73 @@
+//! The `ZCL_*_ID` and `ZCL_*_ATTRIBUTE_TYPE` constants used by the `chip` module.
+//!
+//! Values are as per the Matter specification, and hence identical to the ones of the
+//! SDK headers, which are not part of the bindings. Unlike [`crate::clusters`], these are
+//! available regardless of the data model CHIP was built with.
+
+use crate::clusters::{AttributeId, ClusterId, CommandId};
+
+// Clusters
+
+pub const ZCL_ON_OFF_CLUSTER_ID: ClusterId = ClusterId(0x0006);
+pub const ZCL_LEVEL_CONTROL_CLUSTER_ID: ClusterId = ClusterId(0x0008);
+pub const ZCL_DESCRIPTOR_CLUSTER_ID: ClusterId = ClusterId(0x001D);
+pub const ZCL_BRIDGED_DEVICE_BASIC_CLUSTER_ID: ClusterId = ClusterId(0x0039);
+pub const ZCL_TARGET_NAVIGATOR_CLUSTER_ID: ClusterId = ClusterId(0x0505);
+pub const ZCL_MEDIA_PLAYBACK_CLUSTER_ID: ClusterId = ClusterId(0x0506);
+pub const ZCL_KEYPAD_INPUT_CLUSTER_ID: ClusterId = ClusterId(0x0509);
+
+// Attributes
+
+pub const ZCL_ON_OFF_ATTRIBUTE_ID: AttributeId = AttributeId(0x0000);
+
+pub const ZCL_CURRENT_LEVEL_ATTRIBUTE_ID: AttributeId = AttributeId(0x0000);
+pub const ZCL_OPTIONS_ATTRIBUTE_ID: AttributeId = AttributeId(0x000F);
+pub const ZCL_ON_LEVEL_ATTRIBUTE_ID: AttributeId = AttributeId(0x0011);
+
+pub const ZCL_DEVICE_LIST_ATTRIBUTE_ID: AttributeId = AttributeId(0x0000);
+pub const ZCL_SERVER_LIST_ATTRIBUTE_ID: AttributeId = AttributeId(0x0001);
+pub const ZCL_CLIENT_LIST_ATTRIBUTE_ID: AttributeId = AttributeId(0x0002);
+pub const ZCL_PARTS_LIST_ATTRIBUTE_ID: AttributeId = AttributeId(0x0003);
+
+pub const ZCL_NODE_LABEL_ATTRIBUTE_ID: AttributeId = AttributeId(0x0005);
+pub const ZCL_REACHABLE_ATTRIBUTE_ID: AttributeId = AttributeId(0x0011);
+
+pub const ZCL_TARGET_NAVIGATOR_LIST_ATTRIBUTE_ID: AttributeId = AttributeId(0x0000);
+pub const ZCL_TARGET_NAVIGATOR_CURRENT_TARGET_ATTRIBUTE_ID: AttributeId = AttributeId(0x0001);
+
+pub const ZCL_MEDIA_PLAYBACK_STATE_ATTRIBUTE_ID: AttributeId = AttributeId(0x0000);
+
+// Commands
+
+pub const ZCL_OFF_COMMAND_ID: CommandId = CommandId(0x00);
+pub const ZCL_ON_COMMAND_ID: CommandId = CommandId(0x01);
+pub const ZCL_TOGGLE_COMMAND_ID: CommandId = CommandId(0x02);
+
+pub const ZCL_NAVIGATE_TARGET_COMMAND_ID: CommandId = CommandId(0x00);
+pub const ZCL_NAVIGATE_TARGET_RESPONSE_COMMAND_ID: CommandId = CommandId(0x01);
+
+pub const ZCL_PLAY_COMMAND_ID: CommandId = CommandId(0x00);
+pub const ZCL_PAUSE_COMMAND_ID: CommandId = CommandId(0x01);
+pub const ZCL_STOP_COMMAND_ID: CommandId = CommandId(0x02);
+pub const ZCL_PLAYBACK_RESPONSE_COMMAND_ID: CommandId = CommandId(0x0A);
+
+pub const ZCL_SEND_KEY_COMMAND_ID: CommandId = CommandId(0x00);
+pub const ZCL_SEND_KEY_RESPONSE_COMMAND_ID: CommandId = CommandId(0x01);
+
+// Attribute types
+
+pub const ZCL_BOOLEAN_ATTRIBUTE_TYPE: u8 = 0x10;
+pub const ZCL_BITMAP8_ATTRIBUTE_TYPE: u8 = 0x18;
+pub const ZCL_BITMAP16_ATTRIBUTE_TYPE: u8 = 0x19;
+pub const ZCL_BITMAP32_ATTRIBUTE_TYPE: u8 = 0x1B;
+pub const ZCL_BITMAP64_ATTRIBUTE_TYPE: u8 = 0x1F;
+pub const ZCL_INT8U_ATTRIBUTE_TYPE: u8 = 0x20;
+pub const ZCL_INT16U_ATTRIBUTE_TYPE: u8 = 0x21;
+pub const ZCL_INT32U_ATTRIBUTE_TYPE: u8 = 0x23;
+pub const ZCL_INT64U_ATTRIBUTE_TYPE: u8 = 0x27;
+pub const ZCL_INT8S_ATTRIBUTE_TYPE: u8 = 0x28;
+pub const ZCL_INT16S_ATTRIBUTE_TYPE: u8 = 0x29;
+pub const ZCL_INT32S_ATTRIBUTE_TYPE: u8 = 0x2B;
+pub const ZCL_INT64S_ATTRIBUTE_TYPE: u8 = 0x2F;
+pub const ZCL_CHAR_STRING_ATTRIBUTE_TYPE: u8 = 0x42;
+pub const ZCL_ARRAY_ATTRIBUTE_TYPE: u8 = 0x48;
@@ -0,0 +1,27 @@
+//! The unit tests of the modules of the build script, which Cargo does not run for build scripts
+
+extern crate alloc;
+
+#[allow(dead_code)]
+#[path = "../src/idl.rs"]
+mod idl;
+
+#[allow(dead_code)]
+#[path = "../build/codegen.rs"]
+mod codegen;
+
+#[allow(dead_code)]
+#[path = "../build/config.rs"]
+mod config;
+
+#[allow(dead_code)]
+#[path = "../build/features.rs"]
+mod features;
+
+#[allow(dead_code)]
+#[path = "../build/fingerprint.rs"]
+mod fingerprint;
+
+#[allow(dead_code)]
+#[path = "../build/model.rs"]
+mod model;